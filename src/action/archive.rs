@@ -0,0 +1,257 @@
+use std::fs;
+use std::io::{self, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::archive::ArchiveFormat;
+use crate::config::Config;
+use crate::error::print_error;
+use crate::exit_codes::ExitCode;
+use crate::filesystem::{self, strip_current_dir};
+use crate::walk::WorkerResult;
+
+/// A destination for archive members. Directories only get an entry of their own; their
+/// contents are added as separate search results.
+trait ArchiveWriter {
+    fn append(&mut self, source: &Path, name: &str, metadata: &fs::Metadata) -> io::Result<()>;
+
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+struct TarWriter<W: Write> {
+    builder: tar::Builder<W>,
+}
+
+impl<W: Write> TarWriter<W> {
+    fn new(writer: W) -> TarWriter<W> {
+        let mut builder = tar::Builder::new(writer);
+        builder.follow_symlinks(false);
+        TarWriter { builder }
+    }
+}
+
+impl ArchiveWriter for TarWriter<fs::File> {
+    fn append(&mut self, source: &Path, name: &str, _: &fs::Metadata) -> io::Result<()> {
+        self.builder.append_path_with_name(source, name)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        self.builder.into_inner()?.flush()
+    }
+}
+
+impl ArchiveWriter for TarWriter<GzEncoder<fs::File>> {
+    fn append(&mut self, source: &Path, name: &str, _: &fs::Metadata) -> io::Result<()> {
+        self.builder.append_path_with_name(source, name)
+    }
+
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        self.builder.into_inner()?.finish()?.flush()
+    }
+}
+
+struct ZipWriter<W: Write + Seek> {
+    writer: zip::ZipWriter<W>,
+}
+
+impl<W: Write + Seek> ZipWriter<W> {
+    fn options(metadata: &fs::Metadata) -> zip::write::FileOptions {
+        let mut options = zip::write::FileOptions::default();
+
+        if let Ok(modified) = metadata.modified() {
+            use chrono::{Datelike, Timelike};
+
+            let modified = chrono::DateTime::<chrono::Local>::from(modified);
+            if let Ok(time) = zip::DateTime::from_date_and_time(
+                modified.year() as u16,
+                modified.month() as u8,
+                modified.day() as u8,
+                modified.hour() as u8,
+                modified.minute() as u8,
+                modified.second() as u8,
+            ) {
+                options = options.last_modified_time(time);
+            }
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            options = options.unix_permissions(metadata.permissions().mode() & 0o7777);
+        }
+
+        options
+    }
+}
+
+impl<W: Write + Seek> ArchiveWriter for ZipWriter<W> {
+    fn append(&mut self, source: &Path, name: &str, metadata: &fs::Metadata) -> io::Result<()> {
+        let options = Self::options(metadata);
+        let file_type = metadata.file_type();
+        if file_type.is_dir() {
+            self.writer.add_directory(name, options)?;
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(source)?;
+            self.writer
+                .add_symlink(name, target.to_string_lossy(), options)?;
+        } else {
+            let mut reader = fs::File::open(source)?;
+            self.writer.start_file(name, options)?;
+            io::copy(&mut reader, &mut self.writer)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.writer.finish()?.flush()
+    }
+}
+
+/// The name of an archive member: the path relative to its search root, with `/` separators.
+fn member_name(source: &Path, search_paths: &[PathBuf]) -> Option<String> {
+    let root = filesystem::search_root_of(source, search_paths)?;
+    let relative = source.strip_prefix(root).ok()?;
+    let name = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn create_writer(format: ArchiveFormat, path: &Path) -> io::Result<Box<dyn ArchiveWriter>> {
+    let file = fs::File::create(path)?;
+    Ok(match format {
+        ArchiveFormat::Tar => Box::new(TarWriter::new(file)),
+        ArchiveFormat::TarGz => {
+            Box::new(TarWriter::new(GzEncoder::new(file, Compression::default())))
+        }
+        ArchiveFormat::Zip => Box::new(ZipWriter {
+            writer: zip::ZipWriter::new(file),
+        }),
+    })
+}
+
+/// Stream all search results into an archive (`--archive`). Member paths are relative to the
+/// search path the entry was found in.
+pub fn archive(
+    rx: Receiver<WorkerResult>,
+    format: ArchiveFormat,
+    output: &Path,
+    search_paths: &[PathBuf],
+    config: &Config,
+) -> ExitCode {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let mut writer = if config.dry_run {
+        None
+    } else {
+        match create_writer(format, output) {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                print_error(format!(
+                    "Could not create the archive '{}': {}",
+                    output.to_string_lossy(),
+                    e
+                ));
+                return ExitCode::GeneralError;
+            }
+        }
+    };
+
+    let mut exit_code = ExitCode::Success;
+    for value in rx {
+        let source = match value {
+            WorkerResult::Entry(path) => path,
+            WorkerResult::Error(err) => {
+                if config.show_filesystem_errors {
+                    print_error(err.to_string());
+                }
+                continue;
+            }
+        };
+
+        // Never add the archive to itself.
+        if filesystem::is_same_file(&source, output) {
+            continue;
+        }
+
+        let name = match member_name(&source, search_paths) {
+            Some(name) => name,
+            None => {
+                print_error(format!(
+                    "Could not add '{}' to the archive: it is not inside of a search path.",
+                    strip_current_dir(&source).to_string_lossy()
+                ));
+                exit_code = ExitCode::GeneralError;
+                continue;
+            }
+        };
+
+        let writer = match writer {
+            Some(ref mut writer) => writer,
+            None => {
+                let source = strip_current_dir(&source).to_string_lossy();
+                if let Err(e) = writeln!(stdout, "{}  ->  {}", source, name) {
+                    if e.kind() == io::ErrorKind::BrokenPipe {
+                        break;
+                    }
+                }
+                continue;
+            }
+        };
+
+        let result = source
+            .symlink_metadata()
+            .and_then(|metadata| writer.append(&source, &name, &metadata));
+        if let Err(e) = result {
+            print_error(format!(
+                "Could not add '{}' to the archive: {}",
+                strip_current_dir(&source).to_string_lossy(),
+                e
+            ));
+            exit_code = ExitCode::GeneralError;
+        }
+    }
+
+    if let Some(writer) = writer {
+        if let Err(e) = writer.finish() {
+            print_error(format!(
+                "Could not write the archive '{}': {}",
+                output.to_string_lossy(),
+                e
+            ));
+            exit_code = ExitCode::GeneralError;
+        }
+    }
+
+    exit_code
+}
+
+#[cfg(test)]
+mod tests {
+    use super::member_name;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn member_names_are_relative_to_the_search_root() {
+        let roots = vec![PathBuf::from("src"), PathBuf::from("src/nested")];
+        assert_eq!(
+            member_name(Path::new("src/a/b.rs"), &roots),
+            Some("a/b.rs".into())
+        );
+        assert_eq!(
+            member_name(Path::new("src/nested/c.rs"), &roots),
+            Some("c.rs".into())
+        );
+        assert_eq!(member_name(Path::new("src"), &roots), None);
+    }
+}
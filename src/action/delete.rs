@@ -0,0 +1,109 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+
+use super::collect_results;
+use crate::config::Config;
+use crate::error::print_error;
+use crate::exit_codes::ExitCode;
+use crate::filesystem::{self, strip_current_dir};
+use crate::walk::WorkerResult;
+
+/// Check whether deleting `path` would also delete one of the search roots.
+fn contains_search_root(path: &Path, roots: &[PathBuf], recursive: bool) -> bool {
+    if roots
+        .iter()
+        .any(|root| filesystem::is_same_file(path, root))
+    {
+        return true;
+    }
+
+    // With `--recursive`, a directory cannot be deleted if a search root is inside of it.
+    if recursive && path.symlink_metadata().map_or(false, |m| m.is_dir()) {
+        if let Ok(path) = path.canonicalize() {
+            return roots
+                .iter()
+                .filter_map(|root| root.canonicalize().ok())
+                .any(|root| root.starts_with(&path));
+        }
+    }
+
+    false
+}
+
+fn remove(path: &Path, recursive: bool) -> io::Result<()> {
+    let metadata = path.symlink_metadata()?;
+    if metadata.is_dir() {
+        if recursive {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_dir(path)
+        }
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Collect all search results and delete them (`--delete`).
+///
+/// Entries are deleted from the deepest one upwards, so that directories are emptied before
+/// fd attempts to remove them. Without `--recursive`, only empty directories are removed.
+pub fn delete(rx: Receiver<WorkerResult>, roots: &[PathBuf], config: &Config) -> ExitCode {
+    let mut paths = collect_results(rx, config);
+
+    // Children before their parents. Ties are broken by the path to get a stable order.
+    paths.sort_by(|a, b| {
+        b.components()
+            .count()
+            .cmp(&a.components().count())
+            .then_with(|| a.cmp(b))
+    });
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    let mut exit_code = ExitCode::Success;
+    for path in paths {
+        if contains_search_root(&path, roots, config.delete_recursive) {
+            print_error(format!(
+                "Refusing to delete '{}', as it contains a search root.",
+                strip_current_dir(&path).to_string_lossy()
+            ));
+            exit_code = ExitCode::GeneralError;
+            continue;
+        }
+
+        if config.dry_run {
+            if let Err(e) = writeln!(stdout, "{}", strip_current_dir(&path).to_string_lossy()) {
+                if e.kind() == io::ErrorKind::BrokenPipe {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        match remove(&path, config.delete_recursive) {
+            Ok(()) => {}
+            // The entry might have been removed together with a directory that was found
+            // through another search path.
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                let hint = if !config.delete_recursive && fs::read_dir(&path).is_ok() {
+                    " (use '--recursive' to delete non-empty directories)"
+                } else {
+                    ""
+                };
+                print_error(format!(
+                    "Could not delete '{}': {}{}",
+                    strip_current_dir(&path).to_string_lossy(),
+                    e,
+                    hint
+                ));
+                exit_code = ExitCode::GeneralError;
+            }
+        }
+    }
+
+    exit_code
+}
@@ -0,0 +1,57 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+
+use crate::config::Config;
+use crate::error::print_error;
+use crate::walk::WorkerResult;
+
+pub use self::archive::archive;
+pub use self::delete::delete;
+pub use self::rename::{rename, RenameTemplate};
+pub use self::transfer::{transfer, TransferMode};
+
+mod archive;
+mod delete;
+mod rename;
+mod transfer;
+
+/// Wait for the search to finish and return all results. Errors are reported on the way.
+fn collect_results(rx: Receiver<WorkerResult>, config: &Config) -> Vec<PathBuf> {
+    rx.into_iter()
+        .filter_map(|value| match value {
+            WorkerResult::Entry(path) => Some(path),
+            WorkerResult::Error(err) => {
+                if config.show_filesystem_errors {
+                    print_error(err.to_string());
+                }
+                None
+            }
+        })
+        .collect()
+}
+
+/// Print a table of planned `source -> target` operations.
+fn print_plan<'a, I>(plan: I) -> io::Result<()>
+where
+    I: Iterator<Item = (&'a Path, &'a Path)> + Clone,
+{
+    let width = plan
+        .clone()
+        .map(|(source, _)| source.to_string_lossy().chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for (source, target) in plan {
+        writeln!(
+            stdout,
+            "{:<width$}  ->  {}",
+            source.to_string_lossy(),
+            target.to_string_lossy(),
+            width = width
+        )?;
+    }
+    stdout.flush()
+}
@@ -0,0 +1,538 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::Receiver;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Context, Result};
+use lazy_static::lazy_static;
+use regex::bytes::{Captures, Regex};
+
+use super::{collect_results, print_plan};
+use crate::config::Config;
+use crate::error::print_error;
+use crate::exec::{basename, dirname, remove_extension};
+use crate::exit_codes::ExitCode;
+use crate::filesystem::{self, strip_current_dir};
+use crate::walk::WorkerResult;
+
+/// A single piece of a rename template.
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Placeholder,
+    Basename,
+    Parent,
+    NoExt,
+    BasenameNoExt,
+    Group(usize),
+    NamedGroup(String),
+}
+
+/// Describes how the new name of a search result is computed (`--rename`).
+///
+/// The template understands the same placeholders as `--exec`. In addition, it can refer to the
+/// capture groups of the search pattern, either by index (`{1}`) or by name (`{name}`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenameTemplate {
+    parts: Vec<Part>,
+}
+
+impl RenameTemplate {
+    pub fn new(template: &str, pattern: &Regex) -> Result<RenameTemplate> {
+        lazy_static! {
+            static ref PLACEHOLDER_PATTERN: regex::Regex =
+                regex::Regex::new(r"\{(/?\.?|//|[0-9]+|[[:alpha:]_][[:word:]]*)\}").unwrap();
+        }
+
+        if template.is_empty() {
+            return Err(anyhow!("The rename template must not be empty."));
+        }
+
+        let mut parts = Vec::new();
+        let mut start = 0;
+
+        for placeholder in PLACEHOLDER_PATTERN.captures_iter(template) {
+            let whole = placeholder.get(0).unwrap();
+
+            // Leading text before the placeholder.
+            if whole.start() > start {
+                parts.push(Part::Text(template[start..whole.start()].to_owned()));
+            }
+
+            start = whole.end();
+
+            let name = &placeholder[1];
+            let part = match name {
+                "" => Part::Placeholder,
+                "." => Part::NoExt,
+                "/" => Part::Basename,
+                "//" => Part::Parent,
+                "/." => Part::BasenameNoExt,
+                _ if name.bytes().all(|b| b.is_ascii_digit()) => {
+                    let index = name
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|&i| i < pattern.captures_len())
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "The rename template refers to capture group {{{}}}, but the \
+                                 search pattern only has {} capture group(s).",
+                                name,
+                                pattern.captures_len() - 1
+                            )
+                        })?;
+                    Part::Group(index)
+                }
+                _ => {
+                    if !pattern.capture_names().flatten().any(|n| n == name) {
+                        return Err(anyhow!(
+                            "The rename template refers to the capture group '{{{}}}', which is \
+                             not defined in the search pattern.",
+                            name
+                        ));
+                    }
+                    Part::NamedGroup(name.to_owned())
+                }
+            };
+            parts.push(part);
+        }
+
+        // Trailing text after the last placeholder.
+        if start < template.len() {
+            parts.push(Part::Text(template[start..].to_owned()));
+        }
+
+        Ok(RenameTemplate { parts })
+    }
+
+    /// Compute the new path for `path`. A result without any path separators is interpreted
+    /// relative to the parent directory of `path`, so that `{/.}.txt` stays in place.
+    fn generate(&self, path: &Path, captures: Option<&Captures>) -> PathBuf {
+        let mut s = OsString::new();
+        for part in &self.parts {
+            match *part {
+                Part::Text(ref text) => s.push(text),
+                Part::Placeholder => s.push(path),
+                Part::Basename => s.push(basename(path)),
+                Part::Parent => s.push(dirname(path)),
+                Part::NoExt => s.push(remove_extension(path)),
+                Part::BasenameNoExt => s.push(remove_extension(Path::new(basename(path)))),
+                Part::Group(index) => {
+                    if let Some(m) = captures.and_then(|c| c.get(index)) {
+                        s.push(filesystem::osstr_from_bytes(m.as_bytes()));
+                    }
+                }
+                Part::NamedGroup(ref name) => {
+                    if let Some(m) = captures.and_then(|c| c.name(name)) {
+                        s.push(filesystem::osstr_from_bytes(m.as_bytes()));
+                    }
+                }
+            }
+        }
+
+        let new_path = PathBuf::from(s);
+        let target = match path.parent() {
+            Some(parent) if new_path.components().count() == 1 && !new_path.has_root() => {
+                parent.join(new_path)
+            }
+            _ => new_path,
+        };
+        strip_current_dir(&target).to_path_buf()
+    }
+}
+
+/// A planned rename of a single search result.
+#[derive(Debug, PartialEq)]
+struct Rename {
+    source: PathBuf,
+    target: PathBuf,
+}
+
+/// Compute the new names for all sources and check the resulting plan for collisions. On
+/// failure, a list of all problems is returned.
+fn plan(
+    mut sources: Vec<PathBuf>,
+    template: &RenameTemplate,
+    pattern: &Regex,
+    config: &Config,
+) -> std::result::Result<Vec<Rename>, Vec<String>> {
+    sources.sort();
+
+    let mut renames = Vec::with_capacity(sources.len());
+    for source in sources {
+        let source = strip_current_dir(&source).to_path_buf();
+
+        let search_str: Cow<OsStr> = if config.search_full_path {
+            filesystem::path_absolute_form(&source)
+                .map(|p| Cow::Owned(p.into_os_string()))
+                .unwrap_or_else(|_| Cow::Borrowed(source.as_os_str()))
+        } else {
+            Cow::Borrowed(basename(&source))
+        };
+        let search_bytes = filesystem::osstr_to_bytes(search_str.as_ref());
+        let captures = pattern.captures(&search_bytes);
+
+        let target = template.generate(&source, captures.as_ref());
+        if target != source {
+            renames.push(Rename { source, target });
+        }
+    }
+
+    let mut errors = Vec::new();
+    let sources: HashSet<&Path> = renames.iter().map(|r| r.source.as_path()).collect();
+    let mut targets: HashMap<&Path, &Path> = HashMap::new();
+
+    for r in &renames {
+        if let Some(other) = targets.insert(&r.target, &r.source) {
+            errors.push(format!(
+                "Both '{}' and '{}' would be renamed to '{}'.",
+                other.to_string_lossy(),
+                r.source.to_string_lossy(),
+                r.target.to_string_lossy()
+            ));
+        }
+
+        if !sources.contains(r.target.as_path())
+            && r.target.symlink_metadata().is_ok()
+            && !filesystem::is_same_file(&r.source, &r.target)
+        {
+            errors.push(format!(
+                "Renaming '{}' would overwrite the existing '{}'.",
+                r.source.to_string_lossy(),
+                r.target.to_string_lossy()
+            ));
+        }
+
+        if let Some(parent) = r.target.parent().filter(|p| !p.as_os_str().is_empty()) {
+            if !parent.is_dir() {
+                errors.push(format!(
+                    "Cannot rename '{}' to '{}': the directory '{}' does not exist.",
+                    r.source.to_string_lossy(),
+                    r.target.to_string_lossy(),
+                    parent.to_string_lossy()
+                ));
+            }
+        }
+
+        if let Some(ancestor) = r.source.ancestors().skip(1).find(|a| sources.contains(a)) {
+            errors.push(format!(
+                "Cannot rename '{}' together with its parent directory '{}'.",
+                r.source.to_string_lossy(),
+                ancestor.to_string_lossy()
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(renames)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Split the plan into individual `fs::rename` steps. If some targets are sources of other
+/// renames (chains like `a -> b -> c`, or cycles like `a <-> b`), all sources are first moved to
+/// temporary names, so that the order of the steps does not matter.
+fn steps(renames: &[Rename]) -> Vec<(PathBuf, PathBuf)> {
+    let sources: HashSet<&Path> = renames.iter().map(|r| r.source.as_path()).collect();
+    let needs_staging = renames.iter().any(|r| sources.contains(r.target.as_path()));
+
+    if !needs_staging {
+        return renames
+            .iter()
+            .map(|r| (r.source.clone(), r.target.clone()))
+            .collect();
+    }
+
+    let staged: Vec<PathBuf> = renames
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            r.source
+                .with_file_name(format!(".fd-rename-{}-{}", process::id(), i))
+        })
+        .collect();
+
+    renames
+        .iter()
+        .zip(&staged)
+        .map(|(r, tmp)| (r.source.clone(), tmp.clone()))
+        .chain(
+            renames
+                .iter()
+                .zip(&staged)
+                .map(|(r, tmp)| (tmp.clone(), r.target.clone())),
+        )
+        .collect()
+}
+
+/// Perform all steps. If one of them fails, the previous ones are rolled back, so that either
+/// all or none of the renames are applied. Returns the performed steps.
+fn apply(steps: Vec<(PathBuf, PathBuf)>) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut done: Vec<(PathBuf, PathBuf)> = Vec::with_capacity(steps.len());
+
+    for (from, to) in steps {
+        // `fs::rename` silently replaces existing files on some platforms.
+        let result = if to.symlink_metadata().is_ok() && !filesystem::is_same_file(&from, &to) {
+            Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "the target already exists",
+            ))
+        } else {
+            fs::rename(&from, &to)
+        };
+
+        if let Err(err) = result {
+            for (done_from, done_to) in done.iter().rev() {
+                if let Err(e) = fs::rename(done_to, done_from) {
+                    print_error(format!(
+                        "Could not roll back the rename of '{}' to '{}': {}",
+                        done_from.to_string_lossy(),
+                        done_to.to_string_lossy(),
+                        e
+                    ));
+                }
+            }
+            return Err(anyhow!(
+                "Could not rename '{}' to '{}': {}. No entries have been renamed.",
+                from.to_string_lossy(),
+                to.to_string_lossy(),
+                err
+            ));
+        }
+
+        done.push((from, to));
+    }
+
+    Ok(done)
+}
+
+fn write_shell_quoted(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    let bytes = filesystem::osstr_to_bytes(path.as_os_str());
+    let mut chunks = bytes.split(|&b| b == b'\'');
+
+    out.write_all(b"'")?;
+    if let Some(first) = chunks.next() {
+        out.write_all(first)?;
+    }
+    for chunk in chunks {
+        out.write_all(b"'\\''")?;
+        out.write_all(chunk)?;
+    }
+    out.write_all(b"'")
+}
+
+/// Write a shell script that reverts the performed steps.
+fn write_journal(path: &Path, done: &[(PathBuf, PathBuf)]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut buffer = Vec::new();
+    writeln!(buffer, "#!/bin/sh")?;
+    writeln!(
+        buffer,
+        "# Undo journal written by 'fd --rename'. Run it with 'sh' to revert the renames."
+    )?;
+    writeln!(buffer, "set -e")?;
+    for (from, to) in done.iter().rev() {
+        buffer.write_all(b"mv -n -- ")?;
+        write_shell_quoted(&mut buffer, &filesystem::absolute_path(to)?)?;
+        buffer.write_all(b" ")?;
+        write_shell_quoted(&mut buffer, &filesystem::absolute_path(from)?)?;
+        buffer.write_all(b"\n")?;
+    }
+
+    fs::write(path, buffer)?;
+    Ok(())
+}
+
+fn default_journal_path() -> Option<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    dirs_next::cache_dir().map(|dir| {
+        dir.join("fd")
+            .join(format!("rename-{}-{}.sh", timestamp, process::id()))
+    })
+}
+
+/// Collect all search results, show the planned renames and apply them (unless `--dry-run`
+/// is given).
+pub fn rename(
+    rx: Receiver<WorkerResult>,
+    template: &RenameTemplate,
+    pattern: &Regex,
+    config: &Config,
+) -> ExitCode {
+    let sources = collect_results(rx, config);
+
+    let renames = match plan(sources, template, pattern, config) {
+        Ok(renames) => renames,
+        Err(errors) => {
+            for error in errors {
+                print_error(error);
+            }
+            print_error("Nothing has been renamed.");
+            return ExitCode::GeneralError;
+        }
+    };
+
+    if let Err(e) = print_plan(
+        renames
+            .iter()
+            .map(|r| (r.source.as_path(), r.target.as_path())),
+    ) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            print_error(format!("Could not write to output: {}", e));
+            return ExitCode::GeneralError;
+        }
+    }
+
+    if config.dry_run || renames.is_empty() {
+        return ExitCode::Success;
+    }
+
+    let done = match apply(steps(&renames)) {
+        Ok(done) => done,
+        Err(err) => {
+            print_error(err.to_string());
+            return ExitCode::GeneralError;
+        }
+    };
+
+    let journal = config.rename_journal.clone().or_else(default_journal_path);
+    if let Some(journal) = journal {
+        match write_journal(&journal, &done).with_context(|| {
+            format!(
+                "Could not write the undo journal '{}'",
+                journal.to_string_lossy()
+            )
+        }) {
+            Ok(()) => eprintln!("Undo journal written to '{}'.", journal.to_string_lossy()),
+            Err(err) => {
+                print_error(format!("{:#}", err));
+                return ExitCode::GeneralError;
+            }
+        }
+    }
+
+    ExitCode::Success
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(template: &str, pattern: &str) -> RenameTemplate {
+        RenameTemplate::new(template, &Regex::new(pattern).unwrap()).unwrap()
+    }
+
+    fn generate(template: &RenameTemplate, pattern: &str, path: &str) -> PathBuf {
+        let pattern = Regex::new(pattern).unwrap();
+        let path = Path::new(path);
+        let name = filesystem::osstr_to_bytes(basename(path)).into_owned();
+        template.generate(path, pattern.captures(&name).as_ref())
+    }
+
+    #[test]
+    fn template_placeholders() {
+        let t = template("{/.}.jpg", "");
+        assert_eq!(
+            t.parts,
+            vec![Part::BasenameNoExt, Part::Text(".jpg".into())]
+        );
+        assert_eq!(
+            generate(&t, "", "photos/cat.jpeg"),
+            PathBuf::from("photos/cat.jpg")
+        );
+        assert_eq!(generate(&t, "", "cat.jpeg"), PathBuf::from("cat.jpg"));
+    }
+
+    #[test]
+    fn template_with_directories() {
+        let t = template("archive/{/}", "");
+        assert_eq!(
+            generate(&t, "", "photos/cat.jpeg"),
+            PathBuf::from("archive/cat.jpeg")
+        );
+
+        let t = template("{//}/old_{/}", "");
+        assert_eq!(
+            generate(&t, "", "photos/cat.jpeg"),
+            PathBuf::from("photos/old_cat.jpeg")
+        );
+        assert_eq!(generate(&t, "", "cat.jpeg"), PathBuf::from("old_cat.jpeg"));
+    }
+
+    #[test]
+    fn template_capture_groups() {
+        let pattern = r"^IMG_(\d+)\.(?P<ext>\w+)$";
+        let t = template("photo-{1}.{ext}", pattern);
+        assert_eq!(
+            t.parts,
+            vec![
+                Part::Text("photo-".into()),
+                Part::Group(1),
+                Part::Text(".".into()),
+                Part::NamedGroup("ext".into()),
+            ]
+        );
+        assert_eq!(
+            generate(&t, pattern, "dir/IMG_0042.png"),
+            PathBuf::from("dir/photo-0042.png")
+        );
+    }
+
+    #[test]
+    fn template_invalid_capture_groups() {
+        let pattern = Regex::new(r"(\d+)").unwrap();
+        assert!(RenameTemplate::new("{2}", &pattern).is_err());
+        assert!(RenameTemplate::new("{name}", &pattern).is_err());
+        assert!(RenameTemplate::new("", &pattern).is_err());
+        assert!(RenameTemplate::new("{1}", &pattern).is_ok());
+    }
+
+    #[test]
+    fn steps_without_chains_are_direct() {
+        let renames = vec![Rename {
+            source: "a".into(),
+            target: "b".into(),
+        }];
+        assert_eq!(
+            steps(&renames),
+            vec![(PathBuf::from("a"), PathBuf::from("b"))]
+        );
+    }
+
+    #[test]
+    fn steps_with_cycle_are_staged() {
+        let renames = vec![
+            Rename {
+                source: "a".into(),
+                target: "b".into(),
+            },
+            Rename {
+                source: "b".into(),
+                target: "a".into(),
+            },
+        ];
+        let steps = steps(&renames);
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[0].0, PathBuf::from("a"));
+        assert_eq!(steps[1].0, PathBuf::from("b"));
+        assert_eq!(steps[2].1, PathBuf::from("b"));
+        assert_eq!(steps[3].1, PathBuf::from("a"));
+        assert_eq!(steps[0].1, steps[2].0);
+    }
+
+    #[test]
+    fn shell_quoting() {
+        let mut out = Vec::new();
+        write_shell_quoted(&mut out, Path::new("it's here")).unwrap();
+        assert_eq!(out, b"'it'\\''s here'");
+    }
+}
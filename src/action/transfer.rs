@@ -0,0 +1,220 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+
+use filetime::FileTime;
+
+use super::{collect_results, print_plan};
+use crate::config::Config;
+use crate::error::print_error;
+use crate::exit_codes::ExitCode;
+use crate::filesystem::{self, strip_current_dir};
+use crate::walk::WorkerResult;
+
+/// Whether search results are copied or moved (`--copy-to` / `--move-to`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransferMode {
+    Copy,
+    Move,
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, target)
+}
+
+#[cfg(windows)]
+fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    let link_target = fs::read_link(source)?;
+    if source.is_dir() {
+        std::os::windows::fs::symlink_dir(link_target, target)
+    } else {
+        std::os::windows::fs::symlink_file(link_target, target)
+    }
+}
+
+fn copy_file(source: &Path, target: &Path) -> io::Result<()> {
+    let mut reader = fs::File::open(source)?;
+    let mut writer = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)?;
+    io::copy(&mut reader, &mut writer)?;
+    Ok(())
+}
+
+fn preserve_metadata(metadata: &fs::Metadata, target: &Path) -> io::Result<()> {
+    let mtime = FileTime::from_last_modification_time(metadata);
+    if metadata.file_type().is_symlink() {
+        filetime::set_symlink_file_times(target, mtime, mtime)
+    } else {
+        fs::set_permissions(target, metadata.permissions())?;
+        filetime::set_file_mtime(target, mtime)
+    }
+}
+
+/// Copy or move a single file, symlink or directory. Directories are only created at the
+/// target; their contents are transferred as separate search results.
+fn transfer_entry(
+    source: &Path,
+    target: &Path,
+    metadata: &fs::Metadata,
+    mode: TransferMode,
+    preserve: bool,
+) -> io::Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let file_type = metadata.file_type();
+    if file_type.is_dir() {
+        return match fs::create_dir(target) {
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists && target.is_dir() => Ok(()),
+            result => result,
+        };
+    }
+
+    if target.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "the target already exists",
+        ));
+    }
+
+    // Moving within the same file system is a simple rename, which keeps all metadata.
+    if mode == TransferMode::Move && fs::rename(source, target).is_ok() {
+        return Ok(());
+    }
+
+    if file_type.is_symlink() {
+        copy_symlink(source, target)?;
+    } else {
+        copy_file(source, target)?;
+    }
+
+    if preserve || mode == TransferMode::Move {
+        preserve_metadata(metadata, target)?;
+    }
+
+    if mode == TransferMode::Move {
+        fs::remove_file(source)?;
+    }
+
+    Ok(())
+}
+
+/// Collect all search results and copy or move them to `destination`, keeping their path
+/// relative to the search path they were found in.
+pub fn transfer(
+    rx: Receiver<WorkerResult>,
+    mode: TransferMode,
+    destination: &Path,
+    search_paths: &[PathBuf],
+    config: &Config,
+) -> ExitCode {
+    let mut sources = collect_results(rx, config);
+    sources.sort();
+
+    let mut exit_code = ExitCode::Success;
+    let absolute_destination = filesystem::path_absolute_form(destination).ok();
+    let plan: Vec<(PathBuf, PathBuf)> = sources
+        .into_iter()
+        .filter(|source| {
+            // Skip the results of a previous run if the destination is inside a search path.
+            match (
+                &absolute_destination,
+                filesystem::path_absolute_form(source),
+            ) {
+                (Some(dest), Ok(source)) => !source.starts_with(dest),
+                _ => true,
+            }
+        })
+        .filter_map(|source| {
+            let relative = filesystem::search_root_of(&source, search_paths)
+                .and_then(|root| source.strip_prefix(root).ok())
+                .filter(|relative| !relative.as_os_str().is_empty());
+            match relative {
+                Some(relative) => {
+                    let target = destination.join(relative);
+                    Some((source, target))
+                }
+                None => {
+                    print_error(format!(
+                        "Could not {} '{}': it is not inside of a search path.",
+                        if mode == TransferMode::Copy {
+                            "copy"
+                        } else {
+                            "move"
+                        },
+                        strip_current_dir(&source).to_string_lossy()
+                    ));
+                    exit_code = ExitCode::GeneralError;
+                    None
+                }
+            }
+        })
+        .collect();
+
+    if config.dry_run {
+        let display_plan = plan
+            .iter()
+            .map(|(source, target)| (strip_current_dir(source), strip_current_dir(target)));
+        if let Err(e) = print_plan(display_plan) {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                print_error(format!("Could not write to output: {}", e));
+                return ExitCode::GeneralError;
+            }
+        }
+        return exit_code;
+    }
+
+    let mut directories = Vec::new();
+    for (source, target) in plan {
+        let result = source.symlink_metadata().and_then(|metadata| {
+            transfer_entry(&source, &target, &metadata, mode, config.preserve_metadata)?;
+            if metadata.is_dir() {
+                directories.push((source.clone(), target.clone(), metadata));
+            }
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            print_error(format!(
+                "Could not {} '{}' to '{}': {}",
+                if mode == TransferMode::Copy {
+                    "copy"
+                } else {
+                    "move"
+                },
+                strip_current_dir(&source).to_string_lossy(),
+                target.to_string_lossy(),
+                e
+            ));
+            exit_code = ExitCode::GeneralError;
+        }
+    }
+
+    // Directories are handled last (deepest first), as transferring their contents changes
+    // their modification time and moved directories can only be removed once they are empty.
+    for (source, target, metadata) in directories.iter().rev() {
+        if config.preserve_metadata || mode == TransferMode::Move {
+            if let Err(e) = preserve_metadata(metadata, target) {
+                print_error(format!(
+                    "Could not preserve the metadata of '{}': {}",
+                    target.to_string_lossy(),
+                    e
+                ));
+                exit_code = ExitCode::GeneralError;
+            }
+        }
+
+        // Directories that still contain entries which were not part of the search results
+        // are kept.
+        if mode == TransferMode::Move {
+            let _ = fs::remove_dir(source);
+        }
+    }
+
+    exit_code
+}
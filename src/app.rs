@@ -113,10 +113,29 @@ pub fn build_app() -> App<'static, 'static> {
                 .help("Glob-based search (default: regular expression)")
                 .long_help("Perform a glob-based search instead of a regular expression search."),
         )
+        .arg(
+            Arg::with_name("path-glob")
+                .long("path-glob")
+                .conflicts_with_all(&["fixed-strings", "full-path", "fuzzy", "rename"])
+                .overrides_with_all(&["glob", "path-glob"])
+                .hidden_short_help(true)
+                .help("Glob-based search on the path relative to the search path")
+                .long_help(
+                    "Perform a glob-based search on the path of each entry relative to the \
+                     search path it was found in, like the patterns of a '.gitignore' file. \
+                     '**' matches any number of directories, '{a,b}' matches either 'a' or 'b' \
+                     and '[a-z]' matches a single character of a class. A pattern without a \
+                     '/' matches the file name at any depth, a pattern with a '/' is anchored \
+                     at the search path.\n\n\
+                     Examples:\n  \
+                       fd --path-glob 'src/**/*.rs'\n  \
+                       fd --path-glob '**/tests/*.{json,yaml}' config",
+                ),
+        )
         .arg(
             Arg::with_name("regex")
                 .long("regex")
-                .overrides_with_all(&["glob", "regex"])
+                .overrides_with_all(&["glob", "path-glob", "regex"])
                 .hidden_short_help(true)
                 .help("Regular-expression based search (default)")
                 .long_help(
@@ -138,6 +157,62 @@ pub fn build_app() -> App<'static, 'static> {
                      exact filename, consider using '--glob'.",
                 ),
         )
+        .arg(
+            Arg::with_name("fuzzy")
+                .long("fuzzy")
+                .conflicts_with_all(&["glob", "regex", "fixed-strings", "rename"])
+                .help("Fuzzy search, results are ranked by how well they match")
+                .long_help(
+                    "Treat the pattern as a fuzzy query: an entry matches if its name (or its \
+                     full path, with '--full-path') contains all characters of the query in the \
+                     same order. Matches at the start of words and consecutive matches are \
+                     preferred. The results are printed in the order of how well they match, \
+                     which means that they are only shown after the search has finished. \
+                     Combine with '--max-results' to only show the best matches.",
+                ),
+        )
+        .arg(
+            Arg::with_name("normalize")
+                .long("normalize")
+                .takes_value(true)
+                .value_name("form")
+                .possible_values(&["nfc", "nfd", "nfkc"])
+                .conflicts_with("rename")
+                .hidden_short_help(true)
+                .help("Normalize file names and the pattern before matching")
+                .long_help(
+                    "Bring file names and the search pattern into the given Unicode normalization \
+                     form before they are compared. This allows to find files regardless of \
+                     whether their names use composed or decomposed characters, e.g. files that \
+                     were created on macOS.\n  \
+                       'nfc':   canonical composition\n  \
+                       'nfd':   canonical decomposition\n  \
+                       'nfkc':  compatibility composition (also matches ligatures, full-width \
+                     characters, ...)",
+                ),
+        )
+        .arg(
+            Arg::with_name("case-fold")
+                .long("case-fold")
+                .conflicts_with_all(&["case-sensitive", "rename"])
+                .hidden_short_help(true)
+                .help("Case-insensitive search with full Unicode case folding")
+                .long_help(
+                    "Perform a case-insensitive search with full Unicode case folding, so that \
+                     e.g. 'strasse' matches 'Straße'.",
+                ),
+        )
+        .arg(
+            Arg::with_name("ignore-diacritics")
+                .long("ignore-diacritics")
+                .conflicts_with("rename")
+                .hidden_short_help(true)
+                .help("Ignore diacritics when matching")
+                .long_help(
+                    "Ignore diacritics in file names and in the search pattern, so that e.g. \
+                     'creme' matches 'crème'.",
+                ),
+        )
         .arg(
             Arg::with_name("absolute-path")
                 .long("absolute-path")
@@ -170,7 +245,10 @@ pub fn build_app() -> App<'static, 'static> {
                 .help("Follow symbolic links")
                 .long_help(
                     "By default, fd does not descend into symlinked directories. Using this \
-                         flag, symbolic links are also traversed.",
+                         flag, symbolic links are also traversed. Directories that can be reached \
+                         through more than one path are only traversed once: the contents of a \
+                         symlinked directory inside of the search path are only reported below \
+                         its real path.",
                 ),
         )
         .arg(
@@ -185,6 +263,24 @@ pub fn build_app() -> App<'static, 'static> {
                          full path.",
                 ),
         )
+        .arg(
+            Arg::with_name("parent-pattern")
+                .long("parent-pattern")
+                .takes_value(true)
+                .value_name("regex")
+                .number_of_values(1)
+                .help("Only show entries below a directory whose name matches a regex")
+                .long_help(
+                    "Only show entries that have an ancestor directory whose name matches the \
+                         given regular expression. Only the directories between the search path \
+                         and the entry are considered, the search path itself is not. Like the \
+                         search pattern, the regex is case-insensitive unless it contains an \
+                         uppercase character or '--case-sensitive' is used.\n\n\
+                         Example:\n\n  \
+                           - Find YAML files somewhere below a 'k8s' directory:\n\n      \
+                               fd --parent-pattern '^k8s$' -e yaml",
+                ),
+        )
         .arg(
             Arg::with_name("null_separator")
                 .long("print0")
@@ -365,6 +461,215 @@ pub fn build_app() -> App<'static, 'static> {
                      "
                 ),
         )
+        .arg(
+            Arg::with_name("rename")
+                .long("rename")
+                .takes_value(true)
+                .value_name("template")
+                .number_of_values(1)
+                .conflicts_with_all(&["exec", "exec-batch", "list-details"])
+                .help("Rename all search results according to a template")
+                .long_help(
+                    "Rename all search results according to the given template. The template \
+                     supports the same placeholders as '--exec'. In addition, '{N}' and '{name}' \
+                     are substituted by the corresponding capture group of the search pattern. \
+                     If the new name does not contain a path separator, the entry stays in its \
+                     directory.\n\
+                     fd first computes all new names and refuses to rename anything if two \
+                     entries would end up with the same name, or if an existing file would be \
+                     overwritten. It then prints a preview of all renames and applies them. If \
+                     one of the renames fails, all previous ones are rolled back. Afterwards, a \
+                     shell script that reverts the renames is written (see '--rename-journal').\n\n\
+                     Examples:\n\n  \
+                       - Change the extension of all *.jpeg files to *.jpg:\n\n      \
+                           fd -e jpeg --rename '{/.}.jpg'\n\n  \
+                       - Reorder date components using capture groups:\n\n      \
+                           fd '^(\\d+)-(\\d+)-(\\d+)\\.log$' --rename '{3}-{2}-{1}.log'",
+                ),
+        )
+        .arg(
+            Arg::with_name("rename-journal")
+                .long("rename-journal")
+                .takes_value(true)
+                .value_name("path")
+                .number_of_values(1)
+                .requires("rename")
+                .hidden_short_help(true)
+                .help("Where to write the undo journal of --rename")
+                .long_help(
+                    "Write the undo journal of '--rename' to the given path. The journal is a \
+                     shell script that reverts all renames. By default, it is written to the \
+                     'fd' folder in the user's cache directory.",
+                ),
+        )
+        .arg(
+            Arg::with_name("delete")
+                .long("delete")
+                .conflicts_with_all(&["exec", "exec-batch", "list-details", "rename"])
+                .help("Delete all search results")
+                .long_help(
+                    "Delete all search results. Files are removed directly and directories are \
+                     removed after their contents, but only if they are empty (see \
+                     '--recursive'). Search paths are never deleted. Use '--dry-run' to list \
+                     the entries that would be deleted.",
+                ),
+        )
+        .arg(
+            Arg::with_name("recursive")
+                .long("recursive")
+                .requires("delete")
+                .hidden_short_help(true)
+                .help("Allow --delete to remove non-empty directories")
+                .long_help(
+                    "Allow '--delete' to remove directories together with all of their \
+                     contents. Matching directories are not traversed any further (as with \
+                     '--prune').",
+                ),
+        )
+        .arg(
+            Arg::with_name("copy-to")
+                .long("copy-to")
+                .takes_value(true)
+                .value_name("dir")
+                .number_of_values(1)
+                .conflicts_with_all(&["exec", "exec-batch", "list-details", "rename", "delete"])
+                .help("Copy all search results to a directory")
+                .long_help(
+                    "Copy all search results to the given directory, keeping their path relative \
+                     to the search path they were found in. Missing parent directories are \
+                     created. Existing files are never overwritten. Directories in the search \
+                     results are created, but their contents are only copied if they are part \
+                     of the search results as well. Entries inside of the destination directory \
+                     are skipped.\n\n\
+                     Example:\n\n  \
+                       - Collect all PDF files below 'papers' in 'backup/papers':\n\n      \
+                           fd -e pdf . papers --copy-to backup",
+                ),
+        )
+        .arg(
+            Arg::with_name("move-to")
+                .long("move-to")
+                .takes_value(true)
+                .value_name("dir")
+                .number_of_values(1)
+                .conflicts_with_all(&[
+                    "exec",
+                    "exec-batch",
+                    "list-details",
+                    "rename",
+                    "delete",
+                    "copy-to",
+                ])
+                .help("Move all search results to a directory")
+                .long_help(
+                    "Move all search results to the given directory, keeping their path relative \
+                     to the search path they were found in (see '--copy-to'). Directories are \
+                     removed from their original location once they are empty.",
+                ),
+        )
+        .arg(
+            Arg::with_name("preserve")
+                .long("preserve")
+                .requires("copy-to")
+                .hidden_short_help(true)
+                .help("Keep permissions and modification times with --copy-to")
+                .long_help(
+                    "Keep the permissions and modification times of the search results when \
+                     copying them with '--copy-to'. Moved entries always keep them.",
+                ),
+        )
+        .arg(
+            Arg::with_name("archive")
+                .long("archive")
+                .takes_value(true)
+                .value_name("file")
+                .number_of_values(1)
+                .conflicts_with_all(&[
+                    "exec",
+                    "exec-batch",
+                    "list-details",
+                    "rename",
+                    "delete",
+                    "copy-to",
+                    "move-to",
+                ])
+                .help("Write all search results to a tar or zip archive")
+                .long_help(
+                    "Write all search results to an archive instead of printing them. The format \
+                     is chosen by the extension of the file name: '.tar', '.tar.gz' (or '.tgz') \
+                     and '.zip' are supported. Member paths are relative to the search path the \
+                     entry was found in. Directories are added without their contents, unless \
+                     those are part of the search results as well. Symbolic links are stored as \
+                     links.\n\n\
+                     Example:\n\n  \
+                       - Archive all Rust sources:\n\n      \
+                           fd -e rs --archive sources.tar.gz",
+                ),
+        )
+        .arg(
+            Arg::with_name("search-archives")
+                .long("search-archives")
+                .conflicts_with_all(&[
+                    "list-details",
+                    "rename",
+                    "delete",
+                    "copy-to",
+                    "move-to",
+                    "archive",
+                ])
+                .help("Search inside of tar and zip archives")
+                .long_help(
+                    "Search the entries of '.tar', '.tar.gz', '.tgz' and '.zip' files found \
+                     during the search as if they were directories. Matching entries are shown \
+                     as 'path/to/archive.zip!/inner/path'. The search pattern as well as the \
+                     '--extension', '--type' and '--size' filters are applied to archive \
+                     entries. Nested archives are not searched.\n\n\
+                     Example:\n\n  \
+                       - Find the releases that contain a library:\n\n      \
+                           fd --search-archives '^libfoo\\.so$' releases/",
+                ),
+        )
+        .arg(
+            Arg::with_name("explain")
+                .long("explain")
+                .takes_value(true)
+                .value_name("path")
+                .number_of_values(1)
+                .conflicts_with_all(&[
+                    "exec",
+                    "exec-batch",
+                    "list-details",
+                    "rename",
+                    "delete",
+                    "copy-to",
+                    "move-to",
+                    "archive",
+                    "max-results",
+                    "max-one-result",
+                    "quiet",
+                ])
+                .help("Show why a path is or is not part of the search results")
+                .long_help(
+                    "Instead of searching, show whether the given path would be part of the \
+                     search results and why: whether it (or one of its parent directories) is \
+                     hidden, which line of which ignore file ('.gitignore', '.ignore', \
+                     '.fdignore', the global fd ignore file or '--ignore-file') matches it, \
+                     whether an '--exclude' pattern matches it, and which filter rejects it. \
+                     All other options are taken into account as usual.\n\n\
+                     Example:\n\n  \
+                       - Find out why a file is missing from a search for Rust files:\n\n      \
+                           fd -e rs --explain src/generated/bindings.rs",
+                ),
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("Only show what an action like --rename or --delete would do")
+                .long_help(
+                    "Only print what '--rename', '--delete', '--copy-to', '--move-to' or \
+                     '--archive' would do, without changing anything on disk.",
+                ),
+        )
         .arg(
             Arg::with_name("exclude")
                 .long("exclude")
@@ -383,6 +688,71 @@ pub fn build_app() -> App<'static, 'static> {
                            --exclude node_modules",
                 ),
         )
+        .arg(
+            Arg::with_name("exclude-regex")
+                .long("exclude-regex")
+                .takes_value(true)
+                .value_name("regex")
+                .number_of_values(1)
+                .multiple(true)
+                .hidden_short_help(true)
+                .help("Exclude entries that match the given regular expression")
+                .long_help(
+                    "Exclude files/directories that match the given regular expression. The \
+                     regex is matched against the file name, or against the absolute path if it \
+                     contains a '/'. Excluded directories are not traversed. The regex is \
+                     case-insensitive unless it contains an uppercase character. Multiple \
+                     exclude regexes can be specified.\n\n\
+                     Examples:\n  \
+                       --exclude-regex '^\\.?cache$'\n  \
+                       --exclude-regex '/vendor/.*\\.min\\.js$'",
+                ),
+        )
+        .arg(
+            Arg::with_name("exclude-if")
+                .long("exclude-if")
+                .takes_value(true)
+                .value_name("condition")
+                .number_of_values(1)
+                .multiple(true)
+                .hidden_short_help(true)
+                .help("Exclude entries that match the given condition")
+                .long_help(
+                    "Exclude files/directories that match the given condition. A condition is a \
+                     comma separated list of tests that all have to match. The tests take the \
+                     same values as the corresponding options:\n   \
+                       'size=<size>'            like --size\n   \
+                       'type=<type>[|<type>]'   like --type\n   \
+                       'changed-within=<time>'  like --changed-within\n   \
+                       'changed-before=<time>'  like --changed-before\n   \
+                       'owner=<user:group>'     like --owner\n\
+                     Excluded directories are not traversed. Multiple conditions can be \
+                     specified.\n\n\
+                     Examples:\n  \
+                       --exclude-if 'type=d,changed-before=1y'\n  \
+                       --exclude-if 'size=+1G'",
+                ),
+        )
+        .arg(
+            Arg::with_name("include")
+                .long("include")
+                .takes_value(true)
+                .value_name("pattern")
+                .number_of_values(1)
+                .multiple(true)
+                .hidden_short_help(true)
+                .help("Show entries that match the given glob pattern, even if ignored")
+                .long_help(
+                    "Show files/directories that match the given glob pattern, even if they are \
+                     hidden or ignored by '.gitignore', '.ignore' or '.fdignore' files. All \
+                     other ignore rules stay in place. Patterns are matched like the ones of \
+                     '--exclude', which still takes precedence. Multiple include patterns can be \
+                     specified.\n\n\
+                     Examples:\n  \
+                       --include 'target/**/*.d'\n  \
+                       --include .env",
+                ),
+        )
         .arg(
             Arg::with_name("ignore-file")
                 .long("ignore-file")
@@ -426,6 +796,50 @@ pub fn build_app() -> App<'static, 'static> {
                          of available CPU cores)",
                 ),
         )
+        .arg(
+            Arg::with_name("traversal")
+                .long("traversal")
+                .takes_value(true)
+                .value_name("order")
+                .possible_values(&["dfs", "bfs"])
+                .conflicts_with_all(&["fuzzy", "include"])
+                .hidden_short_help(true)
+                .help("Traverse in a stable order: 'dfs' or 'bfs'")
+                .long_help(
+                    "Traverse the search paths on a single thread, so that the results are \
+                     shown in a stable order as soon as they are found. The entries of each \
+                     directory are sorted by name.\n  \
+                       'dfs': depth-first, every directory is followed by its contents\n  \
+                       'bfs': breadth-first, all entries of one level come before the entries \
+                              of the next level\n\
+                     This cannot be combined with '--include'. Commands of '--exec' still run \
+                     in parallel unless '--threads=1' is given.",
+                ),
+        )
+        .arg(
+            Arg::with_name("dirs-first")
+                .long("dirs-first")
+                .conflicts_with_all(&["dirs-last", "fuzzy", "include"])
+                .hidden_short_help(true)
+                .help("Show directories before the other entries of their parent")
+                .long_help(
+                    "Show directories before the other entries of their parent directory. \
+                     Implies '--traversal=dfs', unless another traversal order is given.",
+                ),
+        )
+        .arg(
+            Arg::with_name("dirs-last")
+                .long("dirs-last")
+                .conflicts_with_all(&["fuzzy", "include"])
+                .hidden_short_help(true)
+                .help("Show directories after the other entries of their parent")
+                .long_help(
+                    "Show directories after the other entries of their parent directory. With \
+                     depth-first traversal, every directory is also shown after its contents, \
+                     so that children come before their parents, e.g. to delete them in this \
+                     order. Implies '--traversal=dfs', unless another traversal order is given.",
+                ),
+        )
         .arg(
             Arg::with_name("size")
                 .long("size")
@@ -451,7 +865,74 @@ pub fn build_app() -> App<'static, 'static> {
                          'ki': kibibytes (base two, 2^10 = 1024 bytes)\n    \
                          'mi': mebibytes\n    \
                          'gi': gibibytes\n    \
-                         'ti': tebibytes",
+                         'ti': tebibytes\n\
+                     NUM can have a fractional part, like '1.5g'. A range of sizes can be given \
+                     as <NUM><UNIT>..<NUM><UNIT>, which includes both bounds, e.g. '10m..1g'.",
+                ),
+        )
+        .arg(
+            Arg::with_name("larger-than")
+                .long("larger-than")
+                .takes_value(true)
+                .value_name("file")
+                .hidden_short_help(true)
+                .help("Only show files that are larger than the given file")
+                .long_help(
+                    "Only show files that are larger than the given reference file. Can be \
+                     combined with '--size' and '--smaller-than'.",
+                ),
+        )
+        .arg(
+            Arg::with_name("smaller-than")
+                .long("smaller-than")
+                .takes_value(true)
+                .value_name("file")
+                .hidden_short_help(true)
+                .help("Only show files that are smaller than the given file")
+                .long_help(
+                    "Only show files that are smaller than the given reference file. Can be \
+                     combined with '--size' and '--larger-than'.",
+                ),
+        )
+        .arg(
+            Arg::with_name("dir-entries")
+                .long("dir-entries")
+                .takes_value(true)
+                .value_name("count")
+                .number_of_values(1)
+                .allow_hyphen_values(true)
+                .multiple(true)
+                .hidden_short_help(true)
+                .help("Filter directories by their number of entries")
+                .long_help(
+                    "Only show directories with the given number of entries, using the format \
+                     <+-><NUM>. With '+', the directory must contain at least NUM entries, with \
+                     '-' at most NUM entries, and exactly NUM entries otherwise. A range can be \
+                     given as <NUM>..<NUM>. All direct children count, including hidden and \
+                     ignored ones.\n\
+                     Example:\n    \
+                         --dir-entries +1000",
+                ),
+        )
+        .arg(
+            Arg::with_name("dir-size")
+                .long("dir-size")
+                .takes_value(true)
+                .value_name("size")
+                .number_of_values(1)
+                .allow_hyphen_values(true)
+                .multiple(true)
+                .hidden_short_help(true)
+                .help("Filter directories by the size of their contents")
+                .long_help(
+                    "Only show directories whose contents have the given total size, which \
+                     uses the same format as '--size'. All files below the directory count, \
+                     including hidden and ignored ones. Symbolic links are not followed, and \
+                     files with several hard links are only counted once. With '--disk-usage', \
+                     the allocated sizes of the files are added up. With '--one-file-system', \
+                     directories on other file systems do not count.\n\
+                     Example:\n    \
+                         --dir-size +1G",
                 ),
         )
         .arg(
@@ -504,6 +985,165 @@ pub fn build_app() -> App<'static, 'static> {
                          --older 2018-10-27",
                 ),
         )
+        .arg(
+            Arg::with_name("git-status")
+                .long("git-status")
+                .takes_value(true)
+                .value_name("status")
+                .number_of_values(1)
+                .multiple(true)
+                .help("Filter by git status (modified, untracked, staged, conflicted)")
+                .long_help(
+                    "Only show entries with the given status in their git repository. Allowed \
+                     values are 'modified' (changed in the working tree), 'untracked', 'staged' \
+                     (changed in the index) and 'conflicted'. Several statuses can be given as a \
+                     comma-separated list or by repeating the option. Directories match if they \
+                     contain an entry with one of the statuses. Entries outside of git \
+                     repositories never match. Requires 'git' to be installed.\n\n\
+                     Example:\n\n  \
+                       - Lint only changed Rust files:\n\n      \
+                           fd -e rs --git-status=modified,staged,untracked -X cargo clippy",
+                ),
+        )
+        .arg(
+            Arg::with_name("git-ignored-only")
+                .long("git-ignored-only")
+                .conflicts_with("git-status")
+                .help("Only show entries that are ignored by git")
+                .long_help(
+                    "Only show files and directories that git ignores. '.gitignore' files are not \
+                     respected in this mode, so that the ignored entries can be found. Requires \
+                     'git' to be installed.",
+                ),
+        )
+        .arg(
+            Arg::with_name("broken-symlinks")
+                .long("broken-symlinks")
+                .hidden_short_help(true)
+                .help("Only show symbolic links whose target does not exist")
+                .long_help(
+                    "Only show symbolic links whose target does not exist. Use \
+                     '--format \"{} -> {target}\"' to show where the links point to.",
+                ),
+        )
+        .arg(
+            Arg::with_name("symlink-target")
+                .long("symlink-target")
+                .takes_value(true)
+                .value_name("regex")
+                .number_of_values(1)
+                .hidden_short_help(true)
+                .help("Only show symbolic links whose target matches a regex")
+                .long_help(
+                    "Only show symbolic links whose target, as it is stored in the link, matches \
+                     the given regular expression. The regex is case-insensitive unless it \
+                     contains an uppercase character.\n\n\
+                     Example:\n  \
+                       fd --symlink-target '^/opt/'",
+                ),
+        )
+        .arg(
+            Arg::with_name("symlink-escapes")
+                .long("symlink-escapes")
+                .hidden_short_help(true)
+                .help("Only show symbolic links that point outside of the search path")
+                .long_help(
+                    "Only show symbolic links that resolve to a location outside of the search \
+                     path they were found in. Links are resolved completely, including links in \
+                     their target. For broken links, the target is resolved as far as possible.",
+                ),
+        )
+        .arg(
+            Arg::with_name("mime")
+                .long("mime")
+                .takes_value(true)
+                .value_name("type")
+                .number_of_values(1)
+                .multiple(true)
+                .hidden_short_help(true)
+                .help("Filter by MIME type, detected from the file content")
+                .long_help(
+                    "Only show regular files whose content has the given MIME type, like \
+                     'image/png', or one of a group of types, like 'image/*'. The type is detected \
+                     from the first bytes of each file, regardless of its name. Files without a \
+                     known signature are 'text/plain' or 'application/octet-stream'. Multiple \
+                     types can be specified.\n\n\
+                     Example:\n  \
+                       fd --mime 'image/*' --mime application/pdf",
+                ),
+        )
+        .arg(
+            Arg::with_name("kind")
+                .long("kind")
+                .takes_value(true)
+                .value_name("kind")
+                .number_of_values(1)
+                .multiple(true)
+                .hidden_short_help(true)
+                .help("Filter by kind of file (rust, cpp, image, archive, text, ...)")
+                .long_help(
+                    "Only show files of the given kind. Most kinds are groups of file name \
+                     patterns, like 'rust' ('*.rs') or 'cpp' ('*.c', '*.cc', '*.cpp', '*.h', \
+                     '*.hpp', ...). Some kinds can also be recognized by the content of a file, \
+                     which is detected from its first bytes:\n  \
+                       'image':    image files\n  \
+                       'video':    video files\n  \
+                       'audio':    audio files\n  \
+                       'archive':  archives and compressed files\n  \
+                       'text':     UTF-8 text without NUL bytes\n  \
+                       'binary':   everything that is not text\n  \
+                       'elf':      ELF executables and libraries\n\
+                     Use '--kind-list' to show all kinds. Multiple kinds can be specified.",
+                ),
+        )
+        .arg(
+            Arg::with_name("text-only")
+                .long("text-only")
+                .conflicts_with("binary-only")
+                .hidden_short_help(true)
+                .help("Only show text files")
+                .long_help(
+                    "Only show regular files that contain text: the first few kilobytes of the \
+                     file have to be valid UTF-8 and must not contain NUL bytes. Combined with \
+                     '--exec', this allows to skip binary files:\n\n  \
+                       fd --text-only -e c -X clang-format -i",
+                ),
+        )
+        .arg(
+            Arg::with_name("binary-only")
+                .long("binary-only")
+                .hidden_short_help(true)
+                .help("Only show binary files")
+                .long_help(
+                    "Only show regular files that do not contain text (see '--text-only').",
+                ),
+        )
+        .arg(
+            Arg::with_name("kind-add")
+                .long("kind-add")
+                .takes_value(true)
+                .value_name("definition")
+                .number_of_values(1)
+                .multiple(true)
+                .hidden_short_help(true)
+                .help("Define a kind for --kind, like 'proto:*.proto,*.pb'")
+                .long_help(
+                    "Define a kind for '--kind' with a name and a comma separated list of file \
+                     name patterns. If the kind already exists, the patterns are added to it.\n\n\
+                     Example:\n  \
+                       fd --kind-add 'proto:*.proto,*.pb' --kind proto",
+                ),
+        )
+        .arg(
+            Arg::with_name("kind-list")
+                .long("kind-list")
+                .hidden_short_help(true)
+                .help("Show all kinds that can be used with --kind")
+                .long_help(
+                    "Show all kinds that can be used with '--kind', including the ones defined \
+                     with '--kind-add', and exit.",
+                ),
+        )
         .arg(
             Arg::with_name("max-results")
                 .long("max-results")
@@ -514,7 +1154,16 @@ pub fn build_app() -> App<'static, 'static> {
                 // search are non-deterministic. Users might think that they can run the
                 // same search with `--exec rm` attached and get a reliable removal of
                 // the files they saw in the previous search.
-                .conflicts_with_all(&["exec", "exec-batch", "list-details"])
+                .conflicts_with_all(&[
+                    "exec",
+                    "exec-batch",
+                    "list-details",
+                    "rename",
+                    "delete",
+                    "copy-to",
+                    "move-to",
+                    "archive",
+                ])
                 .hidden_short_help(true)
                 .help("Limit number of search results")
                 .long_help("Limit the number of search results to 'count' and quit immediately."),
@@ -524,7 +1173,16 @@ pub fn build_app() -> App<'static, 'static> {
                 .short("1")
                 .hidden_short_help(true)
                 .overrides_with("max-results")
-                .conflicts_with_all(&["exec", "exec-batch", "list-details"])
+                .conflicts_with_all(&[
+                    "exec",
+                    "exec-batch",
+                    "list-details",
+                    "rename",
+                    "delete",
+                    "copy-to",
+                    "move-to",
+                    "archive",
+                ])
                 .help("Limit search to a single result")
                 .long_help("Limit the search to a single result and quit immediately. \
                                 This is an alias for '--max-results=1'.")
@@ -535,7 +1193,17 @@ pub fn build_app() -> App<'static, 'static> {
                 .short("q")
                 .alias("has-results")
                 .hidden_short_help(true)
-                .conflicts_with_all(&["exec", "exec-batch", "list-details", "max-results"])
+                .conflicts_with_all(&[
+                    "exec",
+                    "exec-batch",
+                    "list-details",
+                    "max-results",
+                    "rename",
+                    "delete",
+                    "copy-to",
+                    "move-to",
+                    "archive",
+                ])
                 .help("Print nothing, exit code 0 if match found, 1 otherwise")
                 .long_help(
                     "When the flag is present, the program does not print anything and will \
@@ -580,6 +1248,43 @@ pub fn build_app() -> App<'static, 'static> {
                  is considered a match. If your pattern starts with a dash (-), make sure to \
                  pass '--' first, or it will be considered as a flag (fd -- '-foo').")
         )
+        .arg(
+            Arg::with_name("patterns")
+                .long("pattern")
+                .takes_value(true)
+                .value_name("pattern")
+                .number_of_values(1)
+                .multiple(true)
+                .allow_hyphen_values(true)
+                .conflicts_with_all(&["fuzzy", "rename"])
+                .hidden_short_help(true)
+                .help("Search for entries that match any of several patterns")
+                .long_help(
+                    "Search for entries that match the given pattern. This option can be used \
+                         multiple times to show entries that match any of the patterns. If it is \
+                         used, all positional arguments are search paths. The patterns are \
+                         interpreted like the positional search pattern, i.e. as regular \
+                         expressions, or as globs if '--glob' is used. Use '{pattern}' in \
+                         '--format' to show which pattern matched an entry.\n\n\
+                         Example:\n\n  \
+                           fd --pattern '\\.pem$' --pattern '^id_rsa' . /srv",
+                ),
+        )
+        .arg(
+            Arg::with_name("patterns-from")
+                .long("patterns-from")
+                .takes_value(true)
+                .value_name("file")
+                .number_of_values(1)
+                .conflicts_with_all(&["fuzzy", "rename"])
+                .hidden_short_help(true)
+                .help("Read search patterns from a file, one per line")
+                .long_help(
+                    "Read search patterns from the given file, one per line, and show entries \
+                         that match any of them. Empty lines are skipped. Can be combined with \
+                         '--pattern'. If it is used, all positional arguments are search paths.",
+                ),
+        )
         .arg(
             Arg::with_name("path-separator")
                 .takes_value(true)
@@ -592,6 +1297,37 @@ pub fn build_app() -> App<'static, 'static> {
                          the OS-specific separator ('/' on Unix, '\\' on Windows).",
                 ),
         )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .value_name("template")
+                .conflicts_with_all(&[
+                    "exec",
+                    "exec-batch",
+                    "list-details",
+                    "rename",
+                    "delete",
+                    "copy-to",
+                    "move-to",
+                    "archive",
+                ])
+                .hidden_short_help(true)
+                .help("Print each search result according to a template")
+                .long_help(
+                    "Print each search result according to the given template instead of just \
+                         printing its path. The template understands the same placeholders as \
+                         '--exec'. In addition, '{root}' is replaced by the search path that \
+                         the result was found in, '{pattern}' by the search pattern that \
+                         matched it (see '--pattern'), '{target}' by the target of a \
+                         symbolic link and '{size}' by the size of a file, like '1.5 MB'.\n\
+                         Examples:\n    \
+                             --format '{root}: {}'\n    \
+                             --format '{/.}'\n    \
+                             --format '{} -> {target}'\n    \
+                             --format '{size} {}'",
+                ),
+        )
         .arg(
             Arg::with_name("path")
                 .multiple(true)
@@ -615,6 +1351,41 @@ pub fn build_app() -> App<'static, 'static> {
                          argument. Changes the usage to `fd [FLAGS/OPTIONS] --search-path <path> \
                          --search-path <path2> [<pattern>]`",
                 ),
+        )
+        .arg(
+            Arg::with_name("roots-from")
+                .long("roots-from")
+                .takes_value(true)
+                .value_name("file")
+                .number_of_values(1)
+                .conflicts_with_all(&["path", "search-path"])
+                .hidden_short_help(true)
+                .help("Read the search paths from a file ('-' for stdin)")
+                .long_help(
+                    "Read the search paths from the given file, or from standard input if the \
+                         file is '-'. Paths are separated by newlines, or by null characters if \
+                         the input contains any (as produced by 'fd --print0').",
+                ),
+        )
+        .arg(
+            Arg::with_name("paths-from")
+                .long("paths-from")
+                .takes_value(true)
+                .value_name("file")
+                .number_of_values(1)
+                .conflicts_with_all(&["path", "search-path", "roots-from", "explain", "include"])
+                .help("Filter the paths listed in a file ('-' for stdin) instead of searching")
+                .long_help(
+                    "Instead of searching the file system, apply the search pattern and all \
+                         filters to the paths listed in the given file, or in standard input if \
+                         the file is '-'. Paths are separated by newlines, or by null characters \
+                         if the input contains any. The paths are not traversed, and ignore \
+                         files, hidden files and '--exclude' patterns are not taken into \
+                         account. Paths that do not exist are skipped.\n\n\
+                         Example:\n\n  \
+                           - Find large files that are tracked by git:\n\n      \
+                               git ls-files -z | fd --paths-from - --size +1m",
+                ),
         );
 
     if cfg!(unix) {
@@ -635,6 +1406,62 @@ pub fn build_app() -> App<'static, 'static> {
                          --owner '!john:students'",
                 ),
         );
+        app = app.arg(
+            Arg::with_name("xattr")
+                .long("xattr")
+                .takes_value(true)
+                .value_name("name[=value]")
+                .multiple(true)
+                .number_of_values(1)
+                .hidden_short_help(true)
+                .help("Filter by extended attributes")
+                .long_help(
+                    "Only show files that carry the given extended attribute. With '=value', \
+                     the attribute also needs to have exactly this value. Precede the name \
+                     with a '!' to only show files that do not carry the attribute instead. \
+                     If the option is given multiple times, all conditions have to be met. \
+                     File systems without support for extended attributes are treated as if \
+                     the attribute was missing.\n\
+                     Examples:\n    \
+                         --xattr user.checksum\n    \
+                         --xattr '!user.processed'\n    \
+                         --xattr security.selinux=system_u:object_r:etc_t:s0",
+                ),
+        );
+        app = app.arg(
+            Arg::with_name("has-acl")
+                .long("has-acl")
+                .hidden_short_help(true)
+                .help("Only show files with POSIX ACLs")
+                .long_help(
+                    "Only show files and directories that have a POSIX access control list \
+                     (or a default ACL) with entries beyond the ones that the mode bits \
+                     express.",
+                ),
+        );
+        app = app.arg(
+            Arg::with_name("disk-usage")
+                .long("disk-usage")
+                .hidden_short_help(true)
+                .help("Compare the allocated size with --size")
+                .long_help(
+                    "Make '--size' compare the space that is allocated for files on disk \
+                     instead of their apparent size. Sparse and compressed files take up less \
+                     space than their length, small files usually take up a whole block.",
+                ),
+        );
+        app = app.arg(
+            Arg::with_name("sparse")
+                .long("sparse")
+                .hidden_short_help(true)
+                .help("Only show sparse files")
+                .long_help(
+                    "Only show sparse files: regular files for which less than half of their \
+                     apparent size is allocated on disk, like VM images or database files with \
+                     holes. Files on file systems with transparent compression can match as \
+                     well.",
+                ),
+        );
     }
 
     // Make `--one-file-system` available only on Unix and Windows platforms, as per the
@@ -0,0 +1,200 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Local, NaiveDate, TimeZone};
+use flate2::read::GzDecoder;
+
+/// The supported archive formats, derived from the file extension. Used by `--archive` to
+/// write archives and by `--search-archives` to read them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub fn from_path(path: &Path) -> Option<ArchiveFormat> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MemberKind {
+    File,
+    Directory,
+    Symlink,
+}
+
+/// A single entry inside of an archive.
+#[derive(Debug)]
+pub struct Member {
+    /// The path inside of the archive, with `/` separators and without a trailing slash.
+    pub path: String,
+    pub kind: MemberKind,
+    pub size: u64,
+    pub mode: Option<u32>,
+    pub modified: Option<SystemTime>,
+    /// The owning user and group, if the archive records them.
+    pub owner: Option<(u32, u32)>,
+}
+
+impl Member {
+    /// The last component of the member path.
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// Whether the member is an executable file.
+    pub fn is_executable(&self) -> bool {
+        self.kind == MemberKind::File && self.mode.map_or(false, |mode| mode & 0o111 != 0)
+    }
+}
+
+fn normalize_member_path(path: &str) -> String {
+    path.trim_start_matches("./")
+        .trim_start_matches('/')
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn tar_members<R: Read>(reader: R) -> io::Result<Vec<Member>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut members = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            tar::EntryType::Directory => MemberKind::Directory,
+            tar::EntryType::Symlink => MemberKind::Symlink,
+            tar::EntryType::Regular | tar::EntryType::Continuous | tar::EntryType::Link => {
+                MemberKind::File
+            }
+            // Skip metadata like PAX headers, as well as devices and pipes.
+            _ => continue,
+        };
+
+        let path = normalize_member_path(&entry.path()?.to_string_lossy());
+        if path.is_empty() {
+            continue;
+        }
+
+        let owner = match (header.uid(), header.gid()) {
+            (Ok(uid), Ok(gid)) => Some((uid as u32, gid as u32)),
+            _ => None,
+        };
+        members.push(Member {
+            path,
+            kind,
+            size: header.size()?,
+            mode: header.mode().ok(),
+            modified: header
+                .mtime()
+                .ok()
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            owner,
+        });
+    }
+    Ok(members)
+}
+
+fn zip_members(file: fs::File) -> io::Result<Vec<Member>> {
+    const S_IFMT: u32 = 0o170000;
+    const S_IFLNK: u32 = 0o120000;
+
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut members = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        let mode = file.unix_mode();
+        let kind = if file.is_dir() {
+            MemberKind::Directory
+        } else if mode.map_or(false, |mode| mode & S_IFMT == S_IFLNK) {
+            MemberKind::Symlink
+        } else {
+            MemberKind::File
+        };
+
+        let path = normalize_member_path(file.name());
+        if path.is_empty() {
+            continue;
+        }
+
+        members.push(Member {
+            path,
+            kind,
+            size: file.size(),
+            mode,
+            modified: zip_time(file.last_modified()),
+            // The ownership is only stored in extra fields, which are not read.
+            owner: None,
+        });
+    }
+    Ok(members)
+}
+
+/// Zip archives store the modification time in local time, without a time zone.
+fn zip_time(time: zip::DateTime) -> Option<SystemTime> {
+    let naive = NaiveDate::from_ymd_opt(
+        i32::from(time.year()),
+        u32::from(time.month()),
+        u32::from(time.day()),
+    )?
+    .and_hms_opt(
+        u32::from(time.hour()),
+        u32::from(time.minute()),
+        u32::from(time.second()),
+    )?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(SystemTime::from)
+}
+
+/// List all entries of the archive at `path`.
+pub fn members(path: &Path, format: ArchiveFormat) -> io::Result<Vec<Member>> {
+    let file = fs::File::open(path)?;
+    match format {
+        ArchiveFormat::Tar => tar_members(io::BufReader::new(file)),
+        ArchiveFormat::TarGz => tar_members(GzDecoder::new(io::BufReader::new(file))),
+        ArchiveFormat::Zip => zip_members(file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_member_path, ArchiveFormat};
+    use std::path::Path;
+
+    #[test]
+    fn archive_format_from_extension() {
+        let format = |name| ArchiveFormat::from_path(Path::new(name));
+        assert_eq!(format("out.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(format("dir/out.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("OUT.TGZ"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("out.zip"), Some(ArchiveFormat::Zip));
+        assert_eq!(format("out.gz"), None);
+        assert_eq!(format("zip"), None);
+    }
+
+    #[test]
+    fn member_paths_are_normalized() {
+        assert_eq!(normalize_member_path("./lib/"), "lib");
+        assert_eq!(
+            normalize_member_path("/usr/lib/libfoo.so"),
+            "usr/lib/libfoo.so"
+        );
+        assert_eq!(normalize_member_path("./"), "");
+    }
+}
@@ -1,13 +1,22 @@
 use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use lscolors::LsColors;
-use regex::bytes::RegexSet;
+use regex::bytes::{Regex, RegexSet};
 
+use crate::action::{RenameTemplate, TransferMode};
+use crate::archive::ArchiveFormat;
 use crate::exec::CommandTemplate;
 use crate::filetypes::FileTypes;
+use crate::filter::{
+    ContentFilter, DirectoryFilter, Exclusion, GitFilter, KindFilter, SizeFilter, SymlinkFilter,
+    TimeFilter,
+};
 #[cfg(unix)]
-use crate::filter::OwnerFilter;
-use crate::filter::{SizeFilter, TimeFilter};
+use crate::filter::{OwnerFilter, XattrFilter};
+use crate::format::FormatTemplate;
+use crate::fuzzy::FuzzyMatcher;
+use crate::normalize::NameNormalizer;
+use crate::walk::{DirectoryOrder, Traversal};
 
 /// Configuration options for *fd*.
 pub struct Config {
@@ -18,6 +27,25 @@ pub struct Config {
     /// name).
     pub search_full_path: bool,
 
+    /// Whether to match the pattern against the path relative to the search path (`--path-glob`).
+    pub search_relative_path: bool,
+
+    /// If a value is supplied, only entries with an ancestor directory (below the search path)
+    /// whose name matches this pattern are shown.
+    pub parent_pattern: Option<Regex>,
+
+    /// All search patterns (`--pattern`, `--patterns-from`), as a set of regexes together with
+    /// the patterns as they were given.
+    pub search_patterns: Option<(RegexSet, Vec<String>)>,
+
+    /// If a value is supplied, the pattern is a fuzzy query and the results are ranked by their
+    /// score (`--fuzzy`).
+    pub fuzzy: Option<FuzzyMatcher>,
+
+    /// If a value is supplied, file names are normalized before they are matched against the
+    /// pattern (`--normalize`, `--case-fold`, `--ignore-diacritics`).
+    pub normalizer: Option<NameNormalizer>,
+
     /// Whether to ignore hidden files and directories (or not).
     pub ignore_hidden: bool,
 
@@ -57,6 +85,12 @@ pub struct Config {
     /// The number of threads to use.
     pub threads: usize,
 
+    /// If set, the search paths are traversed on a single thread in this order.
+    pub traversal: Option<Traversal>,
+
+    /// Whether directories come before or after the other entries of their parent directory.
+    pub directory_order: Option<DirectoryOrder>,
+
     /// If true, the program doesn't print anything and will instead return an exit code of 0
     /// if there's at least one match. Otherwise, the exit code will be 1.
     pub quiet: bool,
@@ -85,9 +119,63 @@ pub struct Config {
     /// If a value is supplied, each item found will be used to generate and execute commands.
     pub command: Option<Arc<CommandTemplate>>,
 
+    /// If a value is supplied, all search results are renamed according to this template.
+    pub rename: Option<RenameTemplate>,
+
+    /// Where to write the undo journal of `--rename`. If `None`, it is written to the cache
+    /// directory.
+    pub rename_journal: Option<PathBuf>,
+
+    /// Whether to delete all search results.
+    pub delete: bool,
+
+    /// Whether `--delete` may remove non-empty directories.
+    pub delete_recursive: bool,
+
+    /// If a value is supplied, all search results are copied or moved to the given directory.
+    pub transfer: Option<(TransferMode, PathBuf)>,
+
+    /// Whether `--copy-to` keeps the permissions and modification times of the search results.
+    pub preserve_metadata: bool,
+
+    /// If a value is supplied, only entries with the given git status (or only entries ignored
+    /// by git) are shown.
+    pub git_filter: Option<GitFilter>,
+
+    /// If a value is supplied, only symbolic links that are broken, point to a matching target or
+    /// lead out of the search path are shown.
+    pub symlink_filter: Option<SymlinkFilter>,
+
+    /// If a value is supplied, only regular files whose content has one of the given MIME types
+    /// are shown (`--mime`).
+    pub content_filter: Option<ContentFilter>,
+
+    /// If a value is supplied, only files of one of the given kinds are shown (`--kind`).
+    pub kind_filter: Option<KindFilter>,
+
+    /// If a value is supplied, only text files (`true`) or only binary files (`false`) are shown
+    /// (`--text-only`, `--binary-only`).
+    pub text_files: Option<bool>,
+
+    /// Whether to search the entries of tar and zip archives found during the walk.
+    pub search_archives: bool,
+
+    /// If a value is supplied, all search results are written to an archive at the given path.
+    pub archive: Option<(ArchiveFormat, PathBuf)>,
+
+    /// Whether to only show what an action like `--rename`, `--delete` or `--copy-to` would do,
+    /// without doing it.
+    pub dry_run: bool,
+
     /// A list of glob patterns that should be excluded from the search.
     pub exclude_patterns: Vec<String>,
 
+    /// Regexes and conditions for entries that should be excluded from the search.
+    pub exclusions: Vec<Exclusion>,
+
+    /// A list of glob patterns for entries that are shown even if they are hidden or ignored.
+    pub include_patterns: Vec<String>,
+
     /// A list of custom ignore files.
     pub ignore_files: Vec<PathBuf>,
 
@@ -97,16 +185,32 @@ pub struct Config {
     /// Constraints on last modification time of files
     pub time_constraints: Vec<TimeFilter>,
 
+    /// Whether `--size` compares the allocated size of files instead of their length
+    pub disk_usage: bool,
+
+    /// Whether to only show sparse files
+    pub sparse_only: bool,
+
+    /// Constraints on the number of entries and the content size of directories
+    pub dir_filter: Option<DirectoryFilter>,
+
     #[cfg(unix)]
     /// User/group ownership constraint
     pub owner_constraint: Option<OwnerFilter>,
 
+    #[cfg(unix)]
+    /// Extended attribute and ACL constraints
+    pub xattr_filter: Option<XattrFilter>,
+
     /// Whether or not to display filesystem errors
     pub show_filesystem_errors: bool,
 
     /// The separator used to print file paths.
     pub path_separator: Option<String>,
 
+    /// If a value is supplied, search results are printed according to this template.
+    pub format: Option<FormatTemplate>,
+
     /// The maximum number of search results
     pub max_results: Option<usize>,
 }
@@ -0,0 +1,423 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder, Glob};
+use ignore::overrides::OverrideBuilder;
+use ignore::Match;
+use regex::bytes::Regex;
+
+use crate::config::Config;
+use crate::exit_codes::ExitCode;
+use crate::filesystem::{self, strip_current_dir};
+use crate::walk::{self, DirEntry, Rejection};
+
+/// A rule of an ignore file that matched a path.
+struct IgnoreRule {
+    file: Option<PathBuf>,
+    pattern: String,
+    whitelist: bool,
+}
+
+impl IgnoreRule {
+    fn from_glob(glob: &Glob) -> IgnoreRule {
+        IgnoreRule {
+            file: glob.from().map(Path::to_path_buf),
+            pattern: glob.original().to_string(),
+            whitelist: glob.is_whitelist(),
+        }
+    }
+
+    /// Describe the rule. Ignore files are shown relative to the search path or the current
+    /// directory, if possible.
+    fn describe(&self, root: &Path, search_path: &Path) -> String {
+        match self.file {
+            Some(ref file) => {
+                let line = fs::read_to_string(file).ok().and_then(|contents| {
+                    contents
+                        .lines()
+                        .position(|line| line.trim_end() == self.pattern)
+                        .map(|i| i + 1)
+                });
+                format!(
+                    "'{}' in '{}'{}",
+                    self.pattern,
+                    match file.strip_prefix(root) {
+                        Ok(relative) =>
+                            strip_current_dir(&search_path.join(relative)).to_path_buf(),
+                        Err(_) => env::current_dir()
+                            .ok()
+                            .and_then(|cwd| file.strip_prefix(cwd).ok().map(Path::to_path_buf))
+                            .unwrap_or_else(|| file.clone()),
+                    }
+                    .to_string_lossy(),
+                    line.map(|l| format!(", line {}", l)).unwrap_or_default()
+                )
+            }
+            None => format!("'{}'", self.pattern),
+        }
+    }
+}
+
+fn load_ignore_file(dir: &Path, file: &Path) -> Option<Gitignore> {
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    builder.add(file);
+    builder.build().ok()
+}
+
+/// Find the rule that decides whether the walker ignores `path`, using the same precedence as
+/// the `ignore` crate: `.fdignore` files come before `.ignore` files, before `.gitignore` files,
+/// before `.git/info/exclude`, before the global git ignore file and before the files given
+/// with `--ignore-file`. Within each kind, the file in the deepest directory wins.
+fn find_ignore_rule(
+    config: &Config,
+    root: &Path,
+    path: &Path,
+    walk_path: &Path,
+    is_dir: bool,
+) -> Option<IgnoreRule> {
+    let levels: Vec<&Path> = path
+        .ancestors()
+        .skip(1)
+        .filter(|dir| config.read_parent_ignore || dir.starts_with(root))
+        .collect();
+    let any_git = path
+        .ancestors()
+        .skip(1)
+        .any(|dir| dir.join(".git").exists());
+
+    // Ignore files matching against the absolute path, in order of precedence.
+    let mut matchers = Vec::new();
+    if config.read_fdignore {
+        for name in &[".fdignore", ".ignore"] {
+            matchers.extend(
+                levels
+                    .iter()
+                    .filter_map(|dir| load_ignore_file(dir, &dir.join(name))),
+            );
+        }
+    }
+    if config.read_vcsignore && any_git {
+        // Only the ignore files up to the root of the repository apply.
+        let repository_levels = match levels.iter().position(|dir| dir.join(".git").exists()) {
+            Some(i) => &levels[..=i],
+            None => &levels[..],
+        };
+        matchers.extend(
+            repository_levels
+                .iter()
+                .filter_map(|dir| load_ignore_file(dir, &dir.join(".gitignore"))),
+        );
+        matchers.extend(repository_levels.iter().filter_map(|dir| {
+            load_ignore_file(dir, &dir.join(".git").join("info").join("exclude"))
+        }));
+        matchers.push(GitignoreBuilder::new(root).build_global().0);
+    }
+
+    let rule = matchers
+        .iter()
+        .map(|matcher| matcher.matched(path, is_dir))
+        .find(|m| !m.is_none());
+    if let Some(Match::Ignore(glob)) | Some(Match::Whitelist(glob)) = rule {
+        return Some(IgnoreRule::from_glob(glob));
+    }
+
+    // Explicit ignore files match against the path as it is seen by the walker. Files that are
+    // added later take precedence.
+    let mut explicit_files = Vec::new();
+    if config.read_global_ignore {
+        explicit_files.extend(walk::global_ignore_file());
+    }
+    explicit_files.extend(config.ignore_files.iter().cloned());
+    explicit_files.iter().rev().find_map(|file| {
+        let mut builder = GitignoreBuilder::new("");
+        builder.add(file);
+        let matcher = builder.build().ok()?;
+        match matcher.matched(walk_path, is_dir) {
+            Match::Ignore(glob) | Match::Whitelist(glob) => Some(IgnoreRule::from_glob(glob)),
+            Match::None => None,
+        }
+    })
+}
+
+/// Find the `--exclude` (`!glob`) or `--include` (`glob`) pattern that matches `walk_path`.
+fn find_override_pattern<'a>(
+    patterns: &'a [String],
+    search_path: &Path,
+    walk_path: &Path,
+    is_dir: bool,
+) -> Option<&'a str> {
+    patterns.iter().find_map(|pattern| {
+        let mut builder = OverrideBuilder::new(search_path);
+        builder.add(pattern).ok()?;
+        let matched = builder.build().ok()?;
+        let matched = matched.matched(walk_path, is_dir);
+        let matches = if pattern.starts_with('!') {
+            matched.is_ignore()
+        } else {
+            matched.is_whitelist()
+        };
+        if matches {
+            Some(pattern.trim_start_matches('!'))
+        } else {
+            None
+        }
+    })
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .map_or(false, |name| name.to_string_lossy().starts_with('.'))
+}
+
+/// The result of one of the checks of `--explain`.
+enum Check {
+    /// The check does not apply to any component of the path.
+    Passed,
+    /// The check applies to the given path, but does not prevent it from being shown.
+    Allowed(PathBuf, String),
+    /// The check prevents the given path from being shown.
+    Rejected(PathBuf, String),
+}
+
+impl Check {
+    /// Turn a rejection into a check that does not prevent the path from being shown.
+    fn allow(self) -> Check {
+        match self {
+            Check::Rejected(path, reason) => Check::Allowed(path, reason),
+            check => check,
+        }
+    }
+}
+
+fn print_check<W: Write>(out: &mut W, name: &str, check: &Check, passed: &str) -> io::Result<()> {
+    match check {
+        Check::Passed => writeln!(out, "{:<10}{}", name, passed),
+        Check::Allowed(path, reason) | Check::Rejected(path, reason) => {
+            writeln!(out, "{:<10}'{}' {}", name, path.to_string_lossy(), reason)
+        }
+    }
+}
+
+/// Report why `path` is or is not part of the search results (`--explain`).
+pub fn explain(
+    path: &Path,
+    search_paths: &[PathBuf],
+    pattern: &Regex,
+    config: &Config,
+) -> Result<ExitCode> {
+    if path.symlink_metadata().is_err() {
+        return Err(anyhow!("'{}' does not exist.", path.to_string_lossy()));
+    }
+
+    let absolute = |path: &Path| {
+        filesystem::path_absolute_form(path).map(|p| filesystem::normalize_lexically(&p))
+    };
+    let absolute_path = absolute(path)?;
+
+    let (search_path, absolute_root) = search_paths
+        .iter()
+        .filter_map(|search_path| Some((search_path, absolute(search_path).ok()?)))
+        .filter(|(_, root)| absolute_path.starts_with(root) && &absolute_path != root)
+        .max_by_key(|(_, root)| root.components().count())
+        .ok_or_else(|| {
+            anyhow!(
+                "'{}' is not inside of any of the search paths.",
+                path.to_string_lossy()
+            )
+        })?;
+    let relative_path = absolute_path.strip_prefix(&absolute_root)?;
+
+    // Every directory on the way from the search path to the entry has to be traversed.
+    let mut hidden = Check::Passed;
+    let mut ignored = Check::Passed;
+    let mut excluded = Check::Passed;
+    let mut traversal = Check::Passed;
+    let mut pruned = false;
+    // Whether the path is not traversed for a reason that '--include' does not override.
+    let mut blocked = false;
+
+    let mut walk_path = search_path.clone();
+    let mut absolute_component = absolute_root.clone();
+    let components: Vec<_> = relative_path.components().collect();
+    for (i, component) in components.iter().enumerate() {
+        walk_path.push(component);
+        absolute_component.push(component);
+        let is_last = i + 1 == components.len();
+        let display_path = strip_current_dir(&walk_path).to_path_buf();
+
+        let metadata = if config.follow_links {
+            walk_path
+                .metadata()
+                .or_else(|_| walk_path.symlink_metadata())
+        } else {
+            walk_path.symlink_metadata()
+        };
+        let is_dir = metadata.as_ref().map_or(false, |m| m.is_dir());
+
+        if !is_last && !pruned && metadata.map_or(false, |m| m.file_type().is_symlink()) {
+            traversal = Check::Rejected(
+                display_path.clone(),
+                "is a symbolic link, which is not followed without '--follow'".into(),
+            );
+            pruned = true;
+            blocked = true;
+        }
+
+        // The walker checks the '--exclude' patterns first, then the ignore files and only if
+        // none of them matches, whether the entry is hidden.
+        let exclude =
+            find_override_pattern(&config.exclude_patterns, search_path, &walk_path, is_dir);
+        if let Some(exclude) = exclude {
+            if matches!(excluded, Check::Passed) {
+                let reason = format!("matches '--exclude {}'", exclude);
+                excluded = if pruned {
+                    Check::Allowed(display_path.clone(), reason)
+                } else {
+                    Check::Rejected(display_path.clone(), reason)
+                };
+            }
+            pruned = true;
+            blocked = true;
+            continue;
+        }
+
+        let entry = DirEntry::from_path(walk_path.clone(), i + 1, config.follow_links);
+        if let Some(exclusion) = config.exclusions.iter().find(|e| e.matches(&entry)) {
+            if matches!(excluded, Check::Passed) {
+                let reason = format!("matches '{}'", exclusion.describe());
+                excluded = if pruned {
+                    Check::Allowed(display_path.clone(), reason)
+                } else {
+                    Check::Rejected(display_path.clone(), reason)
+                };
+            }
+            pruned = true;
+            blocked = true;
+            continue;
+        }
+
+        let rule = find_ignore_rule(
+            config,
+            &absolute_root,
+            &absolute_component,
+            &walk_path,
+            is_dir,
+        );
+        match rule {
+            Some(rule) if rule.whitelist => {
+                if matches!(ignored, Check::Passed) {
+                    ignored = Check::Allowed(
+                        display_path.clone(),
+                        format!(
+                            "is whitelisted by {}",
+                            rule.describe(&absolute_root, search_path)
+                        ),
+                    );
+                }
+            }
+            Some(rule) => {
+                if !matches!(ignored, Check::Rejected(..)) {
+                    let reason = format!(
+                        "is ignored by {}",
+                        rule.describe(&absolute_root, search_path)
+                    );
+                    ignored = if pruned {
+                        Check::Allowed(display_path.clone(), reason)
+                    } else {
+                        Check::Rejected(display_path.clone(), reason)
+                    };
+                }
+                pruned = true;
+            }
+            None if is_hidden(&walk_path) && matches!(hidden, Check::Passed) => {
+                if config.ignore_hidden && !pruned {
+                    hidden = Check::Rejected(
+                        display_path.clone(),
+                        "is hidden (use '--hidden' to show it)".into(),
+                    );
+                    pruned = true;
+                } else if !config.ignore_hidden {
+                    hidden = Check::Allowed(display_path.clone(), "is hidden".into());
+                }
+            }
+            None => {}
+        }
+    }
+
+    // Entries that match an '--include' pattern are found by a second traversal, in which hidden
+    // and ignored files are not skipped.
+    let is_dir = walk_path.is_dir();
+    let included = find_override_pattern(&config.include_patterns, search_path, &walk_path, is_dir);
+    if included.is_some() && !blocked {
+        hidden = hidden.allow();
+        ignored = ignored.allow();
+        pruned = false;
+    }
+
+    let entry_path = strip_current_dir(&walk_path).to_path_buf();
+    let depth = components.len();
+    let rejection = if config
+        .max_depth
+        .map_or(false, |max_depth| depth > max_depth)
+    {
+        Some("--max-depth")
+    } else {
+        let entry = DirEntry::from_path(walk_path.clone(), depth, config.follow_links);
+        walk::filter_entry(config, pattern, &entry).map(Rejection::description)
+    };
+    let filters = match rejection {
+        Some(filter) => Check::Rejected(entry_path.clone(), format!("is rejected by {}", filter)),
+        None => Check::Passed,
+    };
+
+    let shown = !pruned && rejection.is_none();
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let result = (|| -> io::Result<()> {
+        writeln!(stdout, "{:<10}{}", "path:", entry_path.to_string_lossy())?;
+        writeln!(stdout, "{:<10}{}", "search:", search_path.to_string_lossy())?;
+        if let Check::Rejected(..) = traversal {
+            print_check(&mut stdout, "symlink:", &traversal, "")?;
+        }
+        print_check(&mut stdout, "hidden:", &hidden, "no")?;
+        print_check(&mut stdout, "ignored:", &ignored, "no ignore rule matches")?;
+        print_check(&mut stdout, "excluded:", &excluded, "no")?;
+        if !config.include_patterns.is_empty() {
+            match included {
+                Some(include) => writeln!(
+                    stdout,
+                    "{:<10}'{}' matches '--include {}'",
+                    "included:",
+                    entry_path.to_string_lossy(),
+                    include
+                )?,
+                None => writeln!(stdout, "{:<10}no", "included:")?,
+            }
+        }
+        if pruned {
+            writeln!(stdout, "{:<10}not checked", "filters:")?;
+        } else {
+            print_check(&mut stdout, "filters:", &filters, "all passed")?;
+        }
+        writeln!(
+            stdout,
+            "{:<10}{}",
+            "result:",
+            if shown { "shown" } else { "not shown" }
+        )
+    })();
+
+    match result {
+        Err(ref e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            Err(anyhow!("Could not write to output: {}", e))
+        }
+        _ => Ok(ExitCode::Success),
+    }
+}
@@ -1,11 +1,11 @@
 use std::borrow::Cow;
 use std::env;
-use std::ffi::OsStr;
+use std::ffi::{OsStr, OsString};
 use std::fs;
 use std::io;
 #[cfg(any(unix, target_os = "redox"))]
 use std::os::unix::fs::{FileTypeExt, PermissionsExt};
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use normpath::PathExt;
 
@@ -69,6 +69,76 @@ pub fn is_empty(entry: &walk::DirEntry) -> bool {
     }
 }
 
+/// Check whether two paths refer to the same file system entry (without following symlinks).
+#[cfg(unix)]
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (a.symlink_metadata(), b.symlink_metadata()) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// The number of bytes that are allocated on disk for a file (`--disk-usage`). This is smaller
+/// than its length for sparse or compressed files, and usually larger for small files because
+/// whole blocks are allocated.
+#[cfg(unix)]
+pub fn allocated_size(md: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    md.blocks() * 512
+}
+
+#[cfg(not(unix))]
+pub fn allocated_size(md: &fs::Metadata) -> u64 {
+    md.len()
+}
+
+/// The device that a file is stored on, if the platform exposes it.
+#[cfg(unix)]
+pub fn device_id(md: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(md.dev())
+}
+
+#[cfg(not(unix))]
+pub fn device_id(_: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// The device and inode number of a file that has more than one hard link, so that its size can
+/// be counted only once.
+#[cfg(unix)]
+pub fn hard_link_id(md: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    if md.nlink() > 1 {
+        Some((md.dev(), md.ino()))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+pub fn hard_link_id(_: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// A regular file is considered to be sparse if less than half of its length is allocated.
+pub fn is_sparse(md: &fs::Metadata) -> bool {
+    md.is_file() && allocated_size(md) < md.len() / 2
+}
+
 #[cfg(any(unix, target_os = "redox"))]
 pub fn is_socket(ft: fs::FileType) -> bool {
     ft.is_socket()
@@ -90,7 +160,7 @@ pub fn is_pipe(_: fs::FileType) -> bool {
 }
 
 #[cfg(any(unix, target_os = "redox"))]
-pub fn osstr_to_bytes(input: &OsStr) -> Cow<[u8]> {
+pub fn osstr_to_bytes(input: &OsStr) -> Cow<'_, [u8]> {
     use std::os::unix::ffi::OsStrExt;
     Cow::Borrowed(input.as_bytes())
 }
@@ -105,11 +175,74 @@ pub fn osstr_to_bytes(input: &OsStr) -> Cow<[u8]> {
     }
 }
 
+#[cfg(any(unix, target_os = "redox"))]
+pub fn osstr_from_bytes(input: &[u8]) -> Cow<'_, OsStr> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(OsStr::from_bytes(input))
+}
+
+#[cfg(windows)]
+pub fn osstr_from_bytes(input: &[u8]) -> Cow<OsStr> {
+    match String::from_utf8_lossy(input) {
+        Cow::Owned(string) => Cow::Owned(string.into()),
+        Cow::Borrowed(string) => Cow::Borrowed(OsStr::new(string)),
+    }
+}
+
 /// Remove the `./` prefix from a path.
 pub fn strip_current_dir(path: &Path) -> &Path {
     path.strip_prefix(".").unwrap_or(path)
 }
 
+/// Resolve `.` and `..` components without accessing the file system.
+pub fn normalize_lexically(path: &Path) -> PathBuf {
+    path.components()
+        .fold(PathBuf::new(), |mut path, component| {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    path.pop();
+                }
+                component => path.push(component),
+            }
+            path
+        })
+}
+
+/// The last `depth` components of `path`, separated by '/'. For an entry that has been found at
+/// the given depth, this is its path relative to the search path.
+pub fn root_relative_path(path: &Path, depth: usize) -> OsString {
+    let names: Vec<_> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+
+    let mut relative = OsString::new();
+    for (i, name) in names[names.len().saturating_sub(depth)..]
+        .iter()
+        .enumerate()
+    {
+        if i > 0 {
+            relative.push("/");
+        }
+        relative.push(name);
+    }
+    relative
+}
+
+/// Find the search path that `path` has been found in. If several search paths contain the
+/// path, the most specific one is returned.
+pub fn search_root_of<'a>(path: &Path, search_paths: &'a [PathBuf]) -> Option<&'a Path> {
+    search_paths
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .map(PathBuf::as_path)
+}
+
 /// Default value for the path_separator, mainly for MSYS/MSYS2, which set the MSYSTEM
 /// environment variable, and we set fd's path separator to '/' rather than Rust's default of '\'.
 ///
@@ -129,8 +262,47 @@ pub fn default_path_separator() -> Option<String> {
 
 #[cfg(test)]
 mod tests {
-    use super::strip_current_dir;
-    use std::path::Path;
+    use super::{root_relative_path, search_root_of, strip_current_dir};
+    use std::ffi::OsString;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn root_relative_path_basic() {
+        assert_eq!(
+            root_relative_path(Path::new("./src/exec/mod.rs"), 2),
+            OsString::from("exec/mod.rs")
+        );
+        assert_eq!(
+            root_relative_path(Path::new("/tmp/foo"), 1),
+            OsString::from("foo")
+        );
+        assert_eq!(
+            root_relative_path(Path::new("../foo/bar"), 5),
+            OsString::from("foo/bar")
+        );
+    }
+
+    #[test]
+    fn search_root_of_nested_roots() {
+        let roots = vec![
+            PathBuf::from("."),
+            PathBuf::from("foo/bar"),
+            PathBuf::from("foo"),
+        ];
+        assert_eq!(
+            search_root_of(Path::new("./baz"), &roots),
+            Some(Path::new("."))
+        );
+        assert_eq!(
+            search_root_of(Path::new("foo/baz"), &roots),
+            Some(Path::new("foo"))
+        );
+        assert_eq!(
+            search_root_of(Path::new("foo/bar/baz"), &roots),
+            Some(Path::new("foo/bar"))
+        );
+        assert_eq!(search_root_of(Path::new("/baz"), &roots), None);
+    }
 
     #[test]
     fn strip_current_dir_basic() {
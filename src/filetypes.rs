@@ -1,7 +1,11 @@
+use anyhow::{anyhow, Result};
+
+use crate::archive::{Member, MemberKind};
 use crate::filesystem;
 use crate::walk;
 
 /// Whether or not to show
+#[derive(Default)]
 pub struct FileTypes {
     pub files: bool,
     pub directories: bool,
@@ -12,21 +16,35 @@ pub struct FileTypes {
     pub empty_only: bool,
 }
 
-impl Default for FileTypes {
-    fn default() -> FileTypes {
-        FileTypes {
-            files: false,
-            directories: false,
-            symlinks: false,
-            sockets: false,
-            pipes: false,
-            executables_only: false,
-            empty_only: false,
+impl FileTypes {
+    /// Parse the values of `--type`.
+    pub fn parse<'a, I: Iterator<Item = &'a str>>(values: I) -> Result<FileTypes> {
+        let mut file_types = FileTypes::default();
+        for value in values {
+            match value {
+                "f" | "file" => file_types.files = true,
+                "d" | "directory" => file_types.directories = true,
+                "l" | "symlink" => file_types.symlinks = true,
+                "x" | "executable" => {
+                    file_types.executables_only = true;
+                    file_types.files = true;
+                }
+                "e" | "empty" => file_types.empty_only = true,
+                "s" | "socket" => file_types.sockets = true,
+                "p" | "pipe" => file_types.pipes = true,
+                _ => return Err(anyhow!("'{}' is not a valid file type.", value)),
+            }
         }
+
+        // If only 'empty' was specified, search for both files and directories:
+        if file_types.empty_only && !(file_types.files || file_types.directories) {
+            file_types.files = true;
+            file_types.directories = true;
+        }
+
+        Ok(file_types)
     }
-}
 
-impl FileTypes {
     pub fn should_ignore(&self, entry: &walk::DirEntry) -> bool {
         if let Some(ref entry_type) = entry.file_type() {
             (!self.files && entry_type.is_file())
@@ -37,7 +55,7 @@ impl FileTypes {
                 || (self.executables_only
                     && !entry
                         .metadata()
-                        .map(|m| filesystem::is_executable(m))
+                        .map(filesystem::is_executable)
                         .unwrap_or(false))
                 || (self.empty_only && !filesystem::is_empty(entry))
                 || !(entry_type.is_file()
@@ -49,4 +67,14 @@ impl FileTypes {
             true
         }
     }
+
+    /// Like `should_ignore`, but for an entry inside of an archive (`--search-archives`).
+    /// Only files can be checked for emptiness.
+    pub fn should_ignore_archive_member(&self, member: &Member) -> bool {
+        (match member.kind {
+            MemberKind::File => !self.files || (self.empty_only && member.size > 0),
+            MemberKind::Directory => !self.directories || self.empty_only,
+            MemberKind::Symlink => !self.symlinks || self.empty_only,
+        }) || (self.executables_only && !member.is_executable())
+    }
 }
@@ -0,0 +1,341 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+/// The number of bytes that are read from the start of a file to determine its content.
+const SNIFF_LENGTH: usize = 8 * 1024;
+
+/// A signature that identifies a file format: all parts have to match at their offsets.
+struct Magic {
+    parts: &'static [(usize, &'static [u8])],
+    mime: &'static str,
+}
+
+const fn magic(parts: &'static [(usize, &'static [u8])], mime: &'static str) -> Magic {
+    Magic { parts, mime }
+}
+
+/// Signatures of common file formats. More specific signatures come first.
+static MAGIC_TABLE: &[Magic] = &[
+    // Images
+    magic(&[(0, b"\x89PNG\r\n\x1a\n")], "image/png"),
+    magic(&[(0, b"\xff\xd8\xff")], "image/jpeg"),
+    magic(&[(0, b"GIF87a")], "image/gif"),
+    magic(&[(0, b"GIF89a")], "image/gif"),
+    magic(&[(0, b"RIFF"), (8, b"WEBP")], "image/webp"),
+    magic(&[(0, b"II*\x00")], "image/tiff"),
+    magic(&[(0, b"MM\x00*")], "image/tiff"),
+    magic(&[(4, b"ftypavif")], "image/avif"),
+    magic(&[(4, b"ftypheic")], "image/heic"),
+    magic(&[(0, b"\x00\x00\x01\x00")], "image/x-icon"),
+    magic(&[(0, b"BM")], "image/bmp"),
+    // Audio
+    magic(&[(0, b"RIFF"), (8, b"WAVE")], "audio/wav"),
+    magic(&[(4, b"ftypM4A ")], "audio/mp4"),
+    magic(&[(0, b"fLaC")], "audio/flac"),
+    magic(&[(0, b"OggS")], "audio/ogg"),
+    magic(&[(0, b"ID3")], "audio/mpeg"),
+    magic(&[(0, b"\xff\xfb")], "audio/mpeg"),
+    // Video
+    magic(&[(0, b"RIFF"), (8, b"AVI ")], "video/x-msvideo"),
+    magic(&[(4, b"ftypqt  ")], "video/quicktime"),
+    magic(&[(4, b"ftyp")], "video/mp4"),
+    magic(&[(0, b"\x1a\x45\xdf\xa3")], "video/x-matroska"),
+    magic(&[(0, b"FLV")], "video/x-flv"),
+    magic(&[(0, b"\x00\x00\x01\xba")], "video/mpeg"),
+    // Archives and compressed files
+    magic(&[(0, b"PK\x03\x04")], "application/zip"),
+    magic(&[(0, b"PK\x05\x06")], "application/zip"),
+    magic(&[(0, b"\x1f\x8b")], "application/gzip"),
+    magic(&[(257, b"ustar")], "application/x-tar"),
+    magic(&[(0, b"BZh")], "application/x-bzip2"),
+    magic(&[(0, b"\xfd7zXZ\x00")], "application/x-xz"),
+    magic(&[(0, b"\x28\xb5\x2f\xfd")], "application/zstd"),
+    magic(&[(0, b"7z\xbc\xaf\x27\x1c")], "application/x-7z-compressed"),
+    magic(&[(0, b"Rar!\x1a\x07")], "application/vnd.rar"),
+    // Executables
+    magic(&[(0, b"\x7fELF")], "application/x-elf"),
+    magic(&[(0, b"\xcf\xfa\xed\xfe")], "application/x-mach-binary"),
+    magic(&[(0, b"\xce\xfa\xed\xfe")], "application/x-mach-binary"),
+    magic(
+        &[(0, b"MZ")],
+        "application/vnd.microsoft.portable-executable",
+    ),
+    magic(&[(0, b"\x00asm")], "application/wasm"),
+    // Documents and other formats
+    magic(&[(0, b"%PDF-")], "application/pdf"),
+    magic(&[(0, b"SQLite format 3\x00")], "application/vnd.sqlite3"),
+];
+
+/// A check of the header of a file, in addition to its signature.
+type HeaderCheck = fn(&[u8]) -> bool;
+
+/// Signatures that are too short to be reliable on their own need further checks of the header.
+static HEADER_CHECKS: &[(&str, HeaderCheck)] = &[
+    ("image/bmp", is_bmp),
+    (
+        "application/vnd.microsoft.portable-executable",
+        is_portable_executable,
+    ),
+];
+
+fn read_u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    let b = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// A bitmap file header is followed by a DIB header, which starts with its own size.
+fn is_bmp(bytes: &[u8]) -> bool {
+    read_u32_le(bytes, 14).map_or(false, |size| {
+        [12, 16, 40, 52, 56, 64, 108, 124].contains(&size)
+    })
+}
+
+/// The DOS header of a PE file points to the PE signature at offset 0x3C.
+fn is_portable_executable(bytes: &[u8]) -> bool {
+    read_u32_le(bytes, 0x3c)
+        .and_then(|offset| bytes.get(offset as usize..))
+        .map_or(false, |b| b.starts_with(b"PE\x00\x00"))
+}
+
+static ARCHIVE_TYPES: &[&str] = &[
+    "application/zip",
+    "application/gzip",
+    "application/x-tar",
+    "application/x-bzip2",
+    "application/x-xz",
+    "application/zstd",
+    "application/x-7z-compressed",
+    "application/vnd.rar",
+];
+
+/// What is known about the content of a file after looking at its first bytes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Content {
+    pub mime: &'static str,
+    pub is_text: bool,
+}
+
+impl Content {
+    pub fn detect(bytes: &[u8]) -> Content {
+        let is_text = is_text(bytes);
+        let mime = MAGIC_TABLE
+            .iter()
+            .find(|magic| {
+                magic.parts.iter().all(|(offset, signature)| {
+                    bytes
+                        .get(*offset..)
+                        .map_or(false, |b| b.starts_with(signature))
+                }) && HEADER_CHECKS
+                    .iter()
+                    .filter(|(mime, _)| *mime == magic.mime)
+                    .all(|(_, check)| check(bytes))
+            })
+            .map(|magic| magic.mime)
+            .unwrap_or(if is_text {
+                "text/plain"
+            } else {
+                "application/octet-stream"
+            });
+        Content { mime, is_text }
+    }
+
+    /// Read the start of the file at `path` and detect its content.
+    pub fn sniff(path: &Path) -> io::Result<Content> {
+        let mut buffer = Vec::with_capacity(SNIFF_LENGTH);
+        File::open(path)?
+            .take(SNIFF_LENGTH as u64)
+            .read_to_end(&mut buffer)?;
+        Ok(Content::detect(&buffer))
+    }
+}
+
+/// A file is considered to be text if its start does not contain NUL bytes and is valid UTF-8.
+/// A multi-byte character that is cut off at the end of the sample is fine.
+fn is_text(bytes: &[u8]) -> bool {
+    if bytes.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// A class of file contents (`--kind`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContentKind {
+    Image,
+    Video,
+    Audio,
+    Archive,
+    Text,
+    Binary,
+    Elf,
+}
+
+impl ContentKind {
+    pub fn matches(self, content: &Content) -> bool {
+        match self {
+            ContentKind::Image => content.mime.starts_with("image/"),
+            ContentKind::Video => content.mime.starts_with("video/"),
+            ContentKind::Audio => content.mime.starts_with("audio/"),
+            ContentKind::Archive => ARCHIVE_TYPES.contains(&content.mime),
+            ContentKind::Text => content.is_text,
+            ContentKind::Binary => !content.is_text,
+            ContentKind::Elf => content.mime == "application/x-elf",
+        }
+    }
+}
+
+/// A MIME type like `image/png`, or a whole group of them like `image/*` or `image`.
+struct MimePattern {
+    main: String,
+    sub: Option<String>,
+}
+
+impl MimePattern {
+    fn parse(pattern: &str) -> Result<MimePattern> {
+        let pattern = pattern.trim().to_lowercase();
+        let (main, sub) = match pattern.find('/') {
+            Some(index) => (&pattern[..index], Some(&pattern[index + 1..])),
+            None => (&pattern[..], None),
+        };
+        let is_token = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        };
+        if !is_token(main) || !sub.map_or(true, |sub| sub == "*" || is_token(sub)) {
+            return Err(anyhow!(
+                "'{}' is not a valid MIME type. Use e.g. 'image/png' or 'image/*'.",
+                pattern
+            ));
+        }
+        Ok(MimePattern {
+            main: main.to_owned(),
+            sub: sub.filter(|sub| *sub != "*").map(str::to_owned),
+        })
+    }
+
+    fn matches(&self, mime: &str) -> bool {
+        let (main, sub) = mime.split_at(mime.find('/').unwrap_or(mime.len()));
+        main == self.main
+            && self
+                .sub
+                .as_ref()
+                .map_or(true, |s| sub.get(1..) == Some(s.as_str()))
+    }
+}
+
+/// Filter for the MIME type of regular files (`--mime`). The type is determined by looking at
+/// the first bytes of a file.
+pub struct ContentFilter {
+    mimes: Vec<MimePattern>,
+}
+
+impl ContentFilter {
+    pub fn new<'a, I: Iterator<Item = &'a str>>(mimes: I) -> Result<Option<ContentFilter>> {
+        let mimes = mimes.map(MimePattern::parse).collect::<Result<Vec<_>>>()?;
+        if mimes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(ContentFilter { mimes }))
+    }
+
+    pub fn matches(&self, content: &Content) -> bool {
+        self.mimes.iter().any(|m| m.matches(content.mime))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_formats() {
+        assert_eq!(
+            Content::detect(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR").mime,
+            "image/png"
+        );
+        assert_eq!(
+            Content::detect(b"RIFF\x24\x00\x00\x00WEBPVP8 ").mime,
+            "image/webp"
+        );
+        assert_eq!(
+            Content::detect(b"RIFF\x24\x00\x00\x00WAVEfmt ").mime,
+            "audio/wav"
+        );
+        assert_eq!(
+            Content::detect(b"\x7fELF\x02\x01\x01\x00").mime,
+            "application/x-elf"
+        );
+
+        let mut tar = vec![0; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(Content::detect(&tar).mime, "application/x-tar");
+
+        // Signatures beyond the end of the file do not match
+        assert_eq!(Content::detect(b"RIFF").mime, "text/plain");
+    }
+
+    #[test]
+    fn short_signatures_check_the_header() {
+        let mut bmp = b"BM\x46\x00\x00\x00\x00\x00\x00\x00\x36\x00\x00\x00".to_vec();
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        assert_eq!(Content::detect(&bmp).mime, "image/bmp");
+
+        let mut pe = vec![0; 0x84];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..].copy_from_slice(b"PE\x00\x00");
+        assert_eq!(
+            Content::detect(&pe).mime,
+            "application/vnd.microsoft.portable-executable"
+        );
+
+        // Text files that happen to start with the same letters
+        assert_eq!(
+            Content::detect(b"BMW 3 series, 2004, 180000 km\n").mime,
+            "text/plain"
+        );
+        assert_eq!(
+            Content::detect(b"MZ: notes on the Zanzibar migration\n").mime,
+            "text/plain"
+        );
+        pe[0x80..].copy_from_slice(b"NE\x00\x00");
+        assert_eq!(Content::detect(&pe).mime, "application/octet-stream");
+    }
+
+    #[test]
+    fn text_and_binary() {
+        assert!(Content::detect(b"fn main() {}\n").is_text);
+        assert!(Content::detect("Grüße".as_bytes()).is_text);
+        assert!(Content::detect(b"").is_text);
+        // A character that is cut off at the end of the sample
+        assert!(Content::detect(&"ü".as_bytes()[..1]).is_text);
+
+        assert!(!Content::detect(b"abc\x00def").is_text);
+        assert!(!Content::detect(b"\xff\xfe\xfd abc").is_text);
+        assert_eq!(
+            Content::detect(b"\x01\x02\xff\xfe").mime,
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn mime_patterns() {
+        let png = Content::detect(b"\x89PNG\r\n\x1a\n");
+        let filter = |mimes: &[&str]| ContentFilter::new(mimes.iter().copied()).unwrap().unwrap();
+
+        assert!(filter(&["image/png"]).matches(&png));
+        assert!(filter(&["IMAGE/*"]).matches(&png));
+        assert!(filter(&["image"]).matches(&png));
+        assert!(!filter(&["image/jpeg", "video"]).matches(&png));
+
+        assert!(ContentFilter::new(["image/"].iter().copied()).is_err());
+        assert!(ContentFilter::new([].iter().copied()).unwrap().is_none());
+    }
+}
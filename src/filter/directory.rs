@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use super::SizeFilter;
+use crate::filesystem;
+
+/// The measured contents of a directory. Files with several hard links are kept apart, so that
+/// they are only counted once when the contents of several directories are combined.
+#[derive(Default)]
+struct Contents {
+    /// The total size of all files with a single link.
+    size: u64,
+    /// The size of every file with several links, by device and inode number.
+    linked: HashMap<(u64, u64), u64>,
+}
+
+impl Contents {
+    fn total(&self) -> u64 {
+        self.size + self.linked.values().sum::<u64>()
+    }
+
+    fn add(&mut self, other: &Contents) {
+        self.size += other.size;
+        self.linked
+            .extend(other.linked.iter().map(|(&id, &size)| (id, size)));
+    }
+}
+
+/// Filter for directories by their contents (`--dir-entries`, `--dir-size`).
+pub struct DirectoryFilter {
+    entries: Vec<SizeFilter>,
+    size: Vec<SizeFilter>,
+    disk_usage: bool,
+    one_file_system: bool,
+    /// The contents of every directory that has been measured so far. Directories are measured
+    /// bottom-up, so the subdirectories of a directory that has been measured do not have to be
+    /// traversed again when the walk reaches them. This keeps one entry per directory below the
+    /// search path in memory, plus one for each ancestor of a file with several hard links.
+    contents: Mutex<HashMap<PathBuf, Arc<Contents>>>,
+}
+
+impl DirectoryFilter {
+    pub fn new(
+        entries: Vec<SizeFilter>,
+        size: Vec<SizeFilter>,
+        disk_usage: bool,
+        one_file_system: bool,
+    ) -> Option<DirectoryFilter> {
+        if entries.is_empty() && size.is_empty() {
+            return None;
+        }
+        Some(DirectoryFilter {
+            entries,
+            size,
+            disk_usage,
+            one_file_system,
+            contents: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Check the directory at `path`. Anything that is not a directory does not match.
+    pub fn matches(&self, path: &Path) -> bool {
+        if !self.entries.is_empty() {
+            let count = match fs::read_dir(path) {
+                Ok(entries) => entries.count() as u64,
+                Err(_) => return false,
+            };
+            if !self.entries.iter().all(|f| f.is_within(count)) {
+                return false;
+            }
+        }
+
+        self.size.is_empty() || {
+            let device = path
+                .symlink_metadata()
+                .ok()
+                .and_then(|m| filesystem::device_id(&m));
+            let size = self.contents(path, device).total();
+            self.size.iter().all(|f| f.is_within(size))
+        }
+    }
+
+    /// The contents of all files below `path`, without following symbolic links. With
+    /// `--one-file-system`, directories on other devices than `device` are skipped.
+    fn contents(&self, path: &Path, device: Option<u64>) -> Arc<Contents> {
+        if let Some(contents) = self.contents.lock().unwrap().get(path) {
+            return Arc::clone(contents);
+        }
+
+        let mut contents = Contents::default();
+        for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if metadata.is_dir() {
+                let subdirectory_device = filesystem::device_id(&metadata);
+                if self.one_file_system && subdirectory_device != device {
+                    continue;
+                }
+                contents.add(&self.contents(&entry.path(), subdirectory_device));
+                continue;
+            }
+
+            let size = if self.disk_usage {
+                filesystem::allocated_size(&metadata)
+            } else {
+                metadata.len()
+            };
+            match filesystem::hard_link_id(&metadata) {
+                Some(id) => {
+                    contents.linked.insert(id, size);
+                }
+                None => contents.size += size,
+            }
+        }
+
+        let contents = Arc::new(contents);
+        self.contents
+            .lock()
+            .unwrap()
+            .insert(path.to_owned(), Arc::clone(&contents));
+        contents
+    }
+}
@@ -0,0 +1,235 @@
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use regex::bytes::Regex;
+
+#[cfg(unix)]
+use super::OwnerFilter;
+use super::{SizeFilter, TimeFilter};
+use crate::archive::{Member, MemberKind};
+use crate::filesystem;
+use crate::filetypes::FileTypes;
+use crate::walk::DirEntry;
+
+/// A single test of an `--exclude-if` condition.
+enum Test {
+    Size(SizeFilter),
+    Type(FileTypes),
+    Time(TimeFilter),
+    #[cfg(unix)]
+    Owner(OwnerFilter),
+}
+
+impl Test {
+    fn parse(now: &SystemTime, test: &str) -> Result<Test> {
+        let (key, value) = match test.find('=') {
+            Some(index) => (test[..index].trim(), test[index + 1..].trim()),
+            None => (test.trim(), ""),
+        };
+        let invalid_value = || anyhow!("'{}' is not a valid value for '{}'.", value, key);
+
+        match key {
+            "size" => SizeFilter::parse(value).map(Test::Size),
+            "type" => FileTypes::parse(value.split('|')).map(Test::Type),
+            "changed-within" => TimeFilter::after(now, value)
+                .map(Test::Time)
+                .ok_or_else(invalid_value),
+            "changed-before" => TimeFilter::before(now, value)
+                .map(Test::Time)
+                .ok_or_else(invalid_value),
+            #[cfg(unix)]
+            "owner" => OwnerFilter::from_string(value)?
+                .map(Test::Owner)
+                .ok_or_else(invalid_value),
+            _ => Err(anyhow!(
+                "'{}' is not a valid condition. Use 'size=', 'type=', 'owner=', \
+                 'changed-within=' or 'changed-before='.",
+                test
+            )),
+        }
+    }
+
+    fn matches(&self, entry: &DirEntry) -> bool {
+        match self {
+            Test::Size(size) => {
+                entry.file_type().map_or(false, |ft| ft.is_file())
+                    && entry.metadata().map_or(false, |m| size.is_within(m.len()))
+            }
+            Test::Type(file_types) => !file_types.should_ignore(entry),
+            Test::Time(time) => entry
+                .metadata()
+                .and_then(|m| m.modified().ok())
+                .map_or(false, |modified| time.applies_to(&modified)),
+            #[cfg(unix)]
+            Test::Owner(owner) => entry.metadata().map_or(false, |m| owner.matches(m)),
+        }
+    }
+
+    fn matches_member(&self, member: &Member) -> bool {
+        match self {
+            Test::Size(size) => member.kind == MemberKind::File && size.is_within(member.size),
+            Test::Type(file_types) => !file_types.should_ignore_archive_member(member),
+            Test::Time(time) => member
+                .modified
+                .map_or(false, |modified| time.applies_to(&modified)),
+            #[cfg(unix)]
+            Test::Owner(owner) => member
+                .owner
+                .map_or(false, |(uid, gid)| owner.matches_ids(uid, gid)),
+        }
+    }
+}
+
+enum Kind {
+    /// A regex that is matched against the file name, or against the full path if it contains a
+    /// path separator (`--exclude-regex`).
+    Regex { regex: Regex, full_path: bool },
+    /// A list of tests that all have to match (`--exclude-if`).
+    Condition(Vec<Test>),
+}
+
+/// A rule that excludes entries from the search. Excluded directories are not traversed.
+pub struct Exclusion {
+    kind: Kind,
+    source: String,
+}
+
+impl Exclusion {
+    pub fn regex(pattern: &str, regex: Regex) -> Exclusion {
+        Exclusion {
+            kind: Kind::Regex {
+                regex,
+                full_path: pattern.contains('/'),
+            },
+            source: pattern.to_owned(),
+        }
+    }
+
+    /// Parse a comma separated list of tests, like `size=+1G,owner=root`.
+    pub fn condition(expression: &str, now: &SystemTime) -> Result<Exclusion> {
+        let tests = expression
+            .split(',')
+            .map(|test| Test::parse(now, test))
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| anyhow!("Invalid '--exclude-if {}': {}", expression, e))?;
+        Ok(Exclusion {
+            kind: Kind::Condition(tests),
+            source: expression.to_owned(),
+        })
+    }
+
+    pub fn matches(&self, entry: &DirEntry) -> bool {
+        match self.kind {
+            Kind::Regex {
+                ref regex,
+                full_path,
+            } => {
+                let path = entry.path();
+                let search_str: Cow<OsStr> = if full_path {
+                    match filesystem::path_absolute_form(path) {
+                        Ok(path) => Cow::Owned(path.into_os_string()),
+                        Err(_) => return false,
+                    }
+                } else {
+                    match path.file_name() {
+                        Some(name) => Cow::Borrowed(name),
+                        None => return false,
+                    }
+                };
+                regex.is_match(&filesystem::osstr_to_bytes(&search_str))
+            }
+            Kind::Condition(ref tests) => tests.iter().all(|test| test.matches(entry)),
+        }
+    }
+
+    /// Whether an archive member, which is reported as `member_path`, is excluded. Like the
+    /// contents of an excluded directory, a member is also excluded if the name of one of the
+    /// directories that contain it matches a regex.
+    pub fn matches_member(&self, member: &Member, member_path: &Path) -> bool {
+        match self.kind {
+            Kind::Regex {
+                ref regex,
+                full_path: true,
+            } => filesystem::path_absolute_form(member_path).map_or(false, |path| {
+                regex.is_match(&filesystem::osstr_to_bytes(path.as_os_str()))
+            }),
+            Kind::Regex {
+                ref regex,
+                full_path: false,
+            } => member
+                .path
+                .split('/')
+                .any(|name| regex.is_match(name.as_bytes())),
+            Kind::Condition(ref tests) => tests.iter().all(|test| test.matches_member(member)),
+        }
+    }
+
+    /// The option that defines this exclusion, for `--explain`.
+    pub fn describe(&self) -> String {
+        match self.kind {
+            Kind::Regex { .. } => format!("--exclude-regex {}", self.source),
+            Kind::Condition(_) => format!("--exclude-if {}", self.source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn parse_conditions() {
+        let now = SystemTime::now();
+        assert!(Exclusion::condition("size=+1G", &now).is_ok());
+        assert!(Exclusion::condition("type=d,changed-before=1y", &now).is_ok());
+        assert!(Exclusion::condition("type=f|l", &now).is_ok());
+        assert!(Exclusion::condition("size>1G", &now).is_err());
+        assert!(Exclusion::condition("size=big", &now).is_err());
+        assert!(Exclusion::condition("type=q", &now).is_err());
+        assert!(Exclusion::condition("", &now).is_err());
+    }
+
+    /// An exclusion, as created from the command line by `--exclude-regex`.
+    fn exclude_regex(pattern: &str) -> Exclusion {
+        let matches =
+            crate::app::build_app().get_matches_from(vec!["fd", "--exclude-regex", pattern]);
+        let regex = crate::build_option_regex(&matches, "--exclude-regex", pattern).unwrap();
+        Exclusion::regex(pattern, regex)
+    }
+
+    fn entry(path: &str) -> DirEntry {
+        DirEntry::from_path(PathBuf::from(path), 2, false)
+    }
+
+    #[test]
+    fn regexes_with_separators_match_the_full_path() {
+        let exclusion = exclude_regex("^target$");
+        assert!(matches!(
+            exclusion.kind,
+            Kind::Regex {
+                full_path: false,
+                ..
+            }
+        ));
+        assert!(exclusion.matches(&entry("project/target")));
+        assert!(!exclusion.matches(&entry("project/targets")));
+        assert!(!exclusion.matches(&entry("target/debug")));
+
+        let exclusion = exclude_regex("vendor/.*\\.js$");
+        assert!(matches!(
+            exclusion.kind,
+            Kind::Regex {
+                full_path: true,
+                ..
+            }
+        ));
+        assert!(exclusion.matches(&entry("project/vendor/lib.min.js")));
+        assert!(!exclusion.matches(&entry("project/vendor/lib.min.css")));
+        assert!(!exclusion.matches(&entry("project/lib.min.js")));
+    }
+}
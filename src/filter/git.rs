@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+
+use crate::error::print_error;
+use crate::filesystem;
+
+const MODIFIED: u8 = 1;
+const UNTRACKED: u8 = 1 << 1;
+const STAGED: u8 = 1 << 2;
+const CONFLICTED: u8 = 1 << 3;
+
+/// The state of a single git repository, as reported by `git status` and `git ls-files`.
+#[derive(Default)]
+struct Repository {
+    /// The status flags of all changed files, relative to the repository root.
+    files: HashMap<PathBuf, u8>,
+    /// The combined status flags of all files below a directory.
+    directories: HashMap<PathBuf, u8>,
+    /// All ignored files and directories.
+    ignored: HashSet<PathBuf>,
+}
+
+fn run_git(root: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("could not run 'git': {}", e))?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(anyhow!(
+            "{}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ))
+    }
+}
+
+fn parse_status_code(x: u8, y: u8) -> u8 {
+    match (x, y) {
+        (b'?', b'?') => UNTRACKED,
+        (b'!', b'!') => 0,
+        (b'D', b'D') | (b'A', b'A') | (b'U', _) | (_, b'U') => CONFLICTED,
+        _ => {
+            let mut flags = 0;
+            if x != b' ' {
+                flags |= STAGED;
+            }
+            if y != b' ' {
+                flags |= MODIFIED;
+            }
+            flags
+        }
+    }
+}
+
+impl Repository {
+    fn load(root: &Path, ignored_only: bool) -> Result<Repository> {
+        let mut repository = Repository::default();
+
+        if ignored_only {
+            let output = run_git(
+                root,
+                &[
+                    "ls-files",
+                    "-z",
+                    "--others",
+                    "--ignored",
+                    "--exclude-standard",
+                    "--directory",
+                ],
+            )?;
+            repository.ignored = output
+                .split(|b| *b == 0)
+                .filter(|path| !path.is_empty())
+                .map(|path| PathBuf::from(filesystem::osstr_from_bytes(path).into_owned()))
+                .collect();
+            return Ok(repository);
+        }
+
+        let output = run_git(
+            root,
+            &["status", "--porcelain", "-z", "--untracked-files=all"],
+        )?;
+        let mut records = output.split(|b| *b == 0).filter(|r| !r.is_empty());
+        while let Some(record) = records.next() {
+            if record.len() < 4 {
+                continue;
+            }
+            let (x, y) = (record[0], record[1]);
+
+            // Renames and copies are followed by the original path.
+            if x == b'R' || x == b'C' {
+                records.next();
+            }
+
+            let flags = parse_status_code(x, y);
+            if flags == 0 {
+                continue;
+            }
+
+            let path = PathBuf::from(filesystem::osstr_from_bytes(&record[3..]).into_owned());
+            for ancestor in path.ancestors().skip(1) {
+                *repository
+                    .directories
+                    .entry(ancestor.to_path_buf())
+                    .or_insert(0) |= flags;
+            }
+            *repository.files.entry(path).or_insert(0) |= flags;
+        }
+
+        Ok(repository)
+    }
+
+    fn flags(&self, path: &Path) -> u8 {
+        self.files.get(path).copied().unwrap_or(0)
+            | self.directories.get(path).copied().unwrap_or(0)
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        path.ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .any(|p| self.ignored.contains(p))
+    }
+}
+
+/// A repository that is loaded on first use. `None` if its state could not be read.
+type LoadedRepository = OnceCell<Option<Repository>>;
+
+/// Filter for the working tree status of git repositories (`--git-status`) and for files that
+/// are ignored by git (`--git-ignored-only`).
+pub struct GitFilter {
+    statuses: u8,
+    ignored_only: bool,
+    /// The repository root of every directory seen so far, or `None` outside of repositories.
+    roots: Mutex<HashMap<PathBuf, Option<PathBuf>>>,
+    repositories: Mutex<HashMap<PathBuf, Arc<LoadedRepository>>>,
+}
+
+impl GitFilter {
+    /// Creates a filter for a list of comma separated statuses.
+    pub fn from_statuses<'a, I: Iterator<Item = &'a str>>(statuses: I) -> Result<GitFilter> {
+        let mut flags = 0;
+        for status in statuses.flat_map(|s| s.split(',')) {
+            flags |= match status.trim() {
+                "modified" => MODIFIED,
+                "untracked" => UNTRACKED,
+                "staged" => STAGED,
+                "conflicted" => CONFLICTED,
+                other => {
+                    return Err(anyhow!(
+                        "'{}' is not a valid git status. Use one of 'modified', 'untracked', \
+                         'staged' or 'conflicted'.",
+                        other
+                    ))
+                }
+            };
+        }
+        Ok(GitFilter::new(flags, false))
+    }
+
+    pub fn ignored_only() -> GitFilter {
+        GitFilter::new(0, true)
+    }
+
+    fn new(statuses: u8, ignored_only: bool) -> GitFilter {
+        GitFilter {
+            statuses,
+            ignored_only,
+            roots: Mutex::new(HashMap::new()),
+            repositories: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Find the root of the repository containing `dir`. The result is cached for `dir` and all
+    /// ancestors that were inspected, so that each directory is only checked once.
+    fn root(&self, dir: &Path) -> Option<PathBuf> {
+        let mut inspected = Vec::new();
+        let mut root = None;
+        for ancestor in dir.ancestors() {
+            if let Some(cached) = self.roots.lock().unwrap().get(ancestor) {
+                root = cached.clone();
+                break;
+            }
+
+            inspected.push(ancestor);
+            if ancestor.join(".git").exists() {
+                root = Some(ancestor.to_path_buf());
+                break;
+            }
+        }
+
+        let mut roots = self.roots.lock().unwrap();
+        for dir in inspected {
+            roots.insert(dir.to_path_buf(), root.clone());
+        }
+        root
+    }
+
+    /// Find the repository containing `path` and load its state, if that did not happen yet.
+    /// `git` runs without holding the global lock, so that different repositories can be loaded
+    /// in parallel.
+    fn repository(&self, path: &Path) -> Option<(PathBuf, Arc<LoadedRepository>)> {
+        let root = self.root(path.parent()?)?;
+        let repository = self
+            .repositories
+            .lock()
+            .unwrap()
+            .entry(root.clone())
+            .or_default()
+            .clone();
+
+        repository.get_or_init(|| match Repository::load(&root, self.ignored_only) {
+            Ok(repository) => Some(repository),
+            Err(e) => {
+                print_error(format!(
+                    "Could not read the state of the git repository '{}': {}",
+                    root.to_string_lossy(),
+                    e
+                ));
+                None
+            }
+        });
+        Some((root, repository))
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        // Resolve '..' components, so that only real ancestors are considered as repository root.
+        let path = match filesystem::path_absolute_form(path) {
+            Ok(path) => filesystem::normalize_lexically(&path),
+            Err(_) => return false,
+        };
+
+        // The repository metadata itself is never part of the working tree.
+        if path
+            .components()
+            .any(|c| c.as_os_str() == OsStr::new(".git"))
+        {
+            return false;
+        }
+
+        let (root, loaded) = match self.repository(&path) {
+            Some(repository) => repository,
+            None => return false,
+        };
+        let repository = match loaded.get() {
+            Some(Some(repository)) => repository,
+            _ => return false,
+        };
+        let relative = match path.strip_prefix(&root) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+
+        if self.ignored_only {
+            repository.is_ignored(relative)
+        } else {
+            repository.flags(relative) & self.statuses != 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes() {
+        assert_eq!(parse_status_code(b'?', b'?'), UNTRACKED);
+        assert_eq!(parse_status_code(b' ', b'M'), MODIFIED);
+        assert_eq!(parse_status_code(b'M', b' '), STAGED);
+        assert_eq!(parse_status_code(b'A', b'M'), STAGED | MODIFIED);
+        assert_eq!(parse_status_code(b'U', b'U'), CONFLICTED);
+        assert_eq!(parse_status_code(b'A', b'A'), CONFLICTED);
+        assert_eq!(parse_status_code(b'D', b'U'), CONFLICTED);
+    }
+
+    #[test]
+    fn parse_statuses() {
+        let filter = GitFilter::from_statuses(vec!["modified,staged"].into_iter()).unwrap();
+        assert_eq!(filter.statuses, MODIFIED | STAGED);
+
+        let filter = GitFilter::from_statuses(vec!["untracked", "conflicted"].into_iter()).unwrap();
+        assert_eq!(filter.statuses, UNTRACKED | CONFLICTED);
+
+        assert!(GitFilter::from_statuses(vec!["changed"].into_iter()).is_err());
+    }
+
+    #[test]
+    fn ignored_directories() {
+        let mut repository = Repository::default();
+        repository.ignored.insert(PathBuf::from("target"));
+        repository.ignored.insert(PathBuf::from("src/generated.rs"));
+
+        assert!(repository.is_ignored(Path::new("target")));
+        assert!(repository.is_ignored(Path::new("target/debug/fd")));
+        assert!(repository.is_ignored(Path::new("src/generated.rs")));
+        assert!(!repository.is_ignored(Path::new("src")));
+        assert!(!repository.is_ignored(Path::new("src/main.rs")));
+    }
+}
@@ -0,0 +1,266 @@
+use std::ffi::OsStr;
+
+use anyhow::{anyhow, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use super::content::{Content, ContentKind};
+
+/// The predefined kinds: their name, the file name patterns and the kind of content that
+/// identifies them, if any.
+static DEFAULT_KINDS: &[(&str, &[&str], Option<ContentKind>)] = &[
+    (
+        "archive",
+        &[
+            "*.zip", "*.tar", "*.gz", "*.tgz", "*.bz2", "*.tbz2", "*.xz", "*.txz", "*.zst", "*.7z",
+            "*.rar",
+        ],
+        Some(ContentKind::Archive),
+    ),
+    (
+        "audio",
+        &[
+            "*.mp3", "*.flac", "*.ogg", "*.opus", "*.wav", "*.m4a", "*.aac",
+        ],
+        Some(ContentKind::Audio),
+    ),
+    ("binary", &[], Some(ContentKind::Binary)),
+    ("c", &["*.c", "*.h"], None),
+    ("cmake", &["CMakeLists.txt", "*.cmake"], None),
+    (
+        "cpp",
+        &[
+            "*.c", "*.cc", "*.cpp", "*.cxx", "*.h", "*.hh", "*.hpp", "*.hxx",
+        ],
+        None,
+    ),
+    ("css", &["*.css", "*.scss", "*.sass", "*.less"], None),
+    ("docker", &["Dockerfile", "*.dockerfile"], None),
+    ("elf", &[], Some(ContentKind::Elf)),
+    ("go", &["*.go"], None),
+    ("html", &["*.html", "*.htm", "*.xhtml"], None),
+    (
+        "image",
+        &[
+            "*.png", "*.jpg", "*.jpeg", "*.gif", "*.bmp", "*.webp", "*.tif", "*.tiff", "*.ico",
+            "*.svg", "*.heic", "*.avif",
+        ],
+        Some(ContentKind::Image),
+    ),
+    ("java", &["*.java"], None),
+    ("js", &["*.js", "*.mjs", "*.cjs", "*.jsx"], None),
+    ("json", &["*.json"], None),
+    ("make", &["Makefile", "GNUmakefile", "*.mk"], None),
+    ("markdown", &["*.md", "*.markdown"], None),
+    ("python", &["*.py", "*.pyi"], None),
+    ("rust", &["*.rs"], None),
+    ("shell", &["*.sh", "*.bash", "*.zsh", "*.fish"], None),
+    ("text", &[], Some(ContentKind::Text)),
+    ("toml", &["*.toml"], None),
+    ("ts", &["*.ts", "*.tsx"], None),
+    (
+        "video",
+        &[
+            "*.mp4", "*.m4v", "*.mkv", "*.webm", "*.avi", "*.mov", "*.flv", "*.mpg", "*.mpeg",
+        ],
+        Some(ContentKind::Video),
+    ),
+    ("yaml", &["*.yaml", "*.yml"], None),
+];
+
+struct KindDefinition {
+    name: String,
+    globs: Vec<String>,
+    content: Option<ContentKind>,
+}
+
+/// All kinds that can be used with `--kind`: the predefined ones and the ones that are defined
+/// with `--kind-add`.
+pub struct KindDefinitions {
+    kinds: Vec<KindDefinition>,
+}
+
+impl KindDefinitions {
+    pub fn predefined() -> KindDefinitions {
+        let kinds = DEFAULT_KINDS
+            .iter()
+            .map(|&(name, globs, content)| KindDefinition {
+                name: name.to_owned(),
+                globs: globs.iter().map(|&g| g.to_owned()).collect(),
+                content,
+            })
+            .collect();
+        KindDefinitions { kinds }
+    }
+
+    /// Add a definition like `proto:*.proto,*.pb`. The patterns are added to an existing kind of
+    /// the same name.
+    pub fn add(&mut self, definition: &str) -> Result<()> {
+        let invalid = || {
+            anyhow!(
+                "Invalid '--kind-add {}': use a name and a comma separated list of file name \
+                 patterns, like 'proto:*.proto,*.pb'.",
+                definition
+            )
+        };
+
+        let index = definition.find(':').ok_or_else(invalid)?;
+        let name = definition[..index].trim();
+        let globs: Vec<String> = definition[index + 1..]
+            .split(',')
+            .map(|glob| glob.trim().to_owned())
+            .collect();
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name
+            || globs
+                .iter()
+                .any(|glob| glob.is_empty() || glob.contains('/'))
+        {
+            return Err(invalid());
+        }
+
+        match self.kinds.iter_mut().find(|kind| kind.name == name) {
+            Some(kind) => kind.globs.extend(globs),
+            None => {
+                self.kinds.push(KindDefinition {
+                    name: name.to_owned(),
+                    globs,
+                    content: None,
+                });
+                self.kinds.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// One line per kind, for `--kind-list`.
+    pub fn list(&self) -> Vec<String> {
+        self.kinds
+            .iter()
+            .map(|kind| {
+                let mut line = format!("{}: {}", kind.name, kind.globs.join(", "));
+                if kind.content.is_some() {
+                    line.push_str(if kind.globs.is_empty() {
+                        "(detected from the content)"
+                    } else {
+                        " (or detected from the content)"
+                    });
+                }
+                line
+            })
+            .collect()
+    }
+
+    pub fn filter<'a, I: Iterator<Item = &'a str>>(&self, names: I) -> Result<KindFilter> {
+        let mut builder = GlobSetBuilder::new();
+        let mut contents = Vec::new();
+        for name in names {
+            let kind = self
+                .kinds
+                .iter()
+                .find(|kind| kind.name == name)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "'{}' is not a known kind. Use '--kind-list' to show all kinds.",
+                        name
+                    )
+                })?;
+
+            for glob in &kind.globs {
+                let glob = GlobBuilder::new(glob)
+                    .case_insensitive(true)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| anyhow!("Malformed pattern of kind '{}': {}", name, e))?;
+                builder.add(glob);
+            }
+            contents.extend(kind.content);
+        }
+
+        Ok(KindFilter {
+            names: builder.build()?,
+            contents,
+        })
+    }
+}
+
+/// Filter for kinds of files (`--kind`). A file has one of the kinds if its name matches one of
+/// their patterns or, for kinds that can be recognized by their content, if its content matches.
+pub struct KindFilter {
+    names: GlobSet,
+    contents: Vec<ContentKind>,
+}
+
+impl KindFilter {
+    /// Whether the name matches, without looking at the content.
+    pub fn matches_name(&self, name: &OsStr) -> bool {
+        self.names.is_match(name)
+    }
+
+    /// Whether a file with the given name matches. The content is only requested if the name
+    /// does not match and one of the kinds can be recognized by the content.
+    pub fn matches<F>(&self, name: &OsStr, content: F) -> bool
+    where
+        F: FnOnce() -> Option<Content>,
+    {
+        if self.matches_name(name) {
+            return true;
+        }
+
+        !self.contents.is_empty()
+            && content().map_or(false, |content| {
+                self.contents.iter().any(|kind| kind.matches(&content))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predefined_kinds() {
+        let definitions = KindDefinitions::predefined();
+        let filter = definitions
+            .filter(vec!["rust", "make"].into_iter())
+            .unwrap();
+        assert!(filter.matches_name(OsStr::new("main.rs")));
+        assert!(filter.matches_name(OsStr::new("Makefile")));
+        assert!(filter.matches_name(OsStr::new("rules.MK")));
+        assert!(!filter.matches_name(OsStr::new("main.rs.orig")));
+        assert!(!filter.matches_name(OsStr::new("Makefile.am")));
+
+        assert!(definitions.filter(vec!["klingon"].into_iter()).is_err());
+    }
+
+    #[test]
+    fn added_kinds() {
+        let mut definitions = KindDefinitions::predefined();
+        definitions.add("proto:*.proto,*.pb").unwrap();
+        definitions.add("rust: Cargo.lock").unwrap();
+        let filter = definitions
+            .filter(vec!["proto", "rust"].into_iter())
+            .unwrap();
+        assert!(filter.matches_name(OsStr::new("api.proto")));
+        assert!(filter.matches_name(OsStr::new("api.pb")));
+        assert!(filter.matches_name(OsStr::new("Cargo.lock")));
+        assert!(filter.matches_name(OsStr::new("lib.rs")));
+
+        assert!(definitions.add("proto").is_err());
+        assert!(definitions.add(":*.proto").is_err());
+        assert!(definitions.add("proto:").is_err());
+        assert!(definitions.add("proto:src/*.proto").is_err());
+    }
+
+    #[test]
+    fn list() {
+        let mut definitions = KindDefinitions::predefined();
+        definitions.add("proto:*.proto,*.pb").unwrap();
+        let list = definitions.list();
+        assert!(list.contains(&"proto: *.proto, *.pb".to_owned()));
+        assert!(list.contains(&"rust: *.rs".to_owned()));
+        assert!(list.contains(&"text: (detected from the content)".to_owned()));
+    }
+}
@@ -1,11 +1,27 @@
-pub use self::size::SizeFilter;
+pub use self::content::{Content, ContentFilter};
+pub use self::directory::DirectoryFilter;
+pub use self::exclusion::Exclusion;
+pub use self::git::GitFilter;
+pub use self::kind::{KindDefinitions, KindFilter};
+pub use self::size::{format_size, SizeFilter};
+pub use self::symlink::SymlinkFilter;
 pub use self::time::TimeFilter;
 
 #[cfg(unix)]
 pub use self::owner::OwnerFilter;
+#[cfg(unix)]
+pub use self::xattr::XattrFilter;
 
+mod content;
+mod directory;
+mod exclusion;
+mod git;
+mod kind;
 mod size;
+mod symlink;
 mod time;
 
 #[cfg(unix)]
 mod owner;
+#[cfg(unix)]
+mod xattr;
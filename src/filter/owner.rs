@@ -52,7 +52,11 @@ impl OwnerFilter {
     pub fn matches(&self, md: &fs::Metadata) -> bool {
         use std::os::unix::fs::MetadataExt;
 
-        self.uid.check(md.uid()) && self.gid.check(md.gid())
+        self.matches_ids(md.uid(), md.gid())
+    }
+
+    pub fn matches_ids(&self, uid: u32, gid: u32) -> bool {
+        self.uid.check(uid) && self.gid.check(gid)
     }
 }
 
@@ -1,8 +1,11 @@
+use std::convert::TryFrom;
+
+use anyhow::{anyhow, Result};
 use lazy_static::lazy_static;
 use regex::Regex;
 
 lazy_static! {
-    static ref SIZE_CAPTURES: Regex = Regex::new(r"(?i)^([+-]?)(\d+)(b|[kmgt]i?b?)$").unwrap();
+    static ref SIZE_CAPTURES: Regex = Regex::new(r"(?i)^(\d+)(?:\.(\d+))?(b|[kmgt]i?b?)$").unwrap();
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -10,6 +13,8 @@ pub enum SizeFilter {
     Max(u64),
     Min(u64),
     Equals(u64),
+    /// An inclusive range, like `10M..1G`
+    Range(u64, u64),
 }
 
 // SI prefixes (powers of 10)
@@ -24,37 +29,48 @@ const MEBI: u64 = KIBI * 1024;
 const GIBI: u64 = MEBI * 1024;
 const TEBI: u64 = GIBI * 1024;
 
+/// More decimal places than this cannot make a difference, even for terabytes.
+const MAX_DECIMALS: usize = 15;
+
 impl SizeFilter {
-    pub fn from_string(s: &str) -> Option<Self> {
-        if !SIZE_CAPTURES.is_match(s) {
-            return None;
-        }
+    /// Parse a size constraint like `+10M`, `-1.5G`, `4ki` or `10M..1G`. The error explains what
+    /// is wrong with the constraint.
+    pub fn parse(s: &str) -> Result<Self> {
+        Self::parse_with(s, parse_size, format_size)
+    }
 
-        let captures = SIZE_CAPTURES.captures(s)?;
-        let limit_kind = captures.get(1).map_or("+", |m| m.as_str());
-        let quantity = captures
-            .get(2)
-            .and_then(|v| v.as_str().parse::<u64>().ok())?;
-
-        let multiplier = match &captures.get(3).map_or("b", |m| m.as_str()).to_lowercase()[..] {
-            v if v.starts_with("ki") => KIBI,
-            v if v.starts_with('k') => KILO,
-            v if v.starts_with("mi") => MEBI,
-            v if v.starts_with('m') => MEGA,
-            v if v.starts_with("gi") => GIBI,
-            v if v.starts_with('g') => GIGA,
-            v if v.starts_with("ti") => TEBI,
-            v if v.starts_with('t') => TERA,
-            "b" => 1,
-            _ => return None,
-        };
+    /// Parse a constraint on a number of items, like `+100`, `-5` or `10..20`. It has the same
+    /// syntax as a size constraint, without a unit.
+    pub fn parse_count(s: &str) -> Result<Self> {
+        Self::parse_with(s, parse_count, |count| count.to_string())
+    }
 
-        let size = quantity * multiplier;
-        match limit_kind {
-            "+" => Some(SizeFilter::Min(size)),
-            "-" => Some(SizeFilter::Max(size)),
-            "" => Some(SizeFilter::Equals(size)),
-            _ => None,
+    fn parse_with(
+        s: &str,
+        parse_bound: fn(&str) -> Result<u64>,
+        format_bound: fn(u64) -> String,
+    ) -> Result<Self> {
+        if let Some(index) = s.find("..") {
+            let lower = parse_bound(&s[..index])?;
+            let upper = parse_bound(&s[index + 2..])?;
+            if lower > upper {
+                return Err(anyhow!(
+                    "Invalid range '{}': the lower bound ({}) is larger than the upper bound \
+                     ({}).",
+                    s,
+                    format_bound(lower),
+                    format_bound(upper)
+                ));
+            }
+            return Ok(SizeFilter::Range(lower, upper));
+        }
+
+        if let Some(bound) = s.strip_prefix('+') {
+            parse_bound(bound).map(SizeFilter::Min)
+        } else if let Some(bound) = s.strip_prefix('-') {
+            parse_bound(bound).map(SizeFilter::Max)
+        } else {
+            parse_bound(s).map(SizeFilter::Equals)
         }
     }
 
@@ -63,10 +79,97 @@ impl SizeFilter {
             SizeFilter::Max(limit) => size <= limit,
             SizeFilter::Min(limit) => size >= limit,
             SizeFilter::Equals(limit) => size == limit,
+            SizeFilter::Range(lower, upper) => lower <= size && size <= upper,
         }
     }
 }
 
+/// Parse a number of bytes with a unit, like `500k` or `1.5G`. Fractions of a byte are dropped.
+fn parse_size(s: &str) -> Result<u64> {
+    let captures = match SIZE_CAPTURES.captures(s) {
+        Some(captures) => captures,
+        None if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit() || c == '.') => {
+            return Err(anyhow!(
+                "The size '{}' has no unit. Use e.g. '{}b' for bytes or '{}k' for kilobytes.",
+                s,
+                s,
+                s
+            ))
+        }
+        None => {
+            return Err(anyhow!(
+                "'{}' is not a valid size. Use a number and a unit, like '500k', '1.5G' or \
+                 '4ki'. See 'fd --help'.",
+                s
+            ))
+        }
+    };
+
+    let multiplier = match &captures[3].to_lowercase()[..] {
+        v if v.starts_with("ki") => KIBI,
+        v if v.starts_with('k') => KILO,
+        v if v.starts_with("mi") => MEBI,
+        v if v.starts_with('m') => MEGA,
+        v if v.starts_with("gi") => GIBI,
+        v if v.starts_with('g') => GIGA,
+        v if v.starts_with("ti") => TEBI,
+        v if v.starts_with('t') => TERA,
+        _ => 1,
+    };
+
+    let too_large = || anyhow!("The size '{}' is too large.", s);
+    let integer = captures[1].parse::<u128>().map_err(|_| too_large())?;
+    let mut size = integer
+        .checked_mul(u128::from(multiplier))
+        .ok_or_else(too_large)?;
+    if let Some(decimals) = captures.get(2) {
+        let decimals = &decimals.as_str()[..decimals.as_str().len().min(MAX_DECIMALS)];
+        let fraction = decimals.parse::<u128>().map_err(|_| too_large())?;
+        size = fraction
+            .checked_mul(u128::from(multiplier))
+            .map(|fraction| fraction / 10u128.pow(decimals.len() as u32))
+            .and_then(|fraction| size.checked_add(fraction))
+            .ok_or_else(too_large)?;
+    }
+
+    u64::try_from(size).map_err(|_| too_large())
+}
+
+fn parse_count(s: &str) -> Result<u64> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!(
+            "'{}' is not a valid number. Use e.g. '+100' or '10..20'. See 'fd --help'.",
+            s
+        ));
+    }
+    s.parse()
+        .map_err(|_| anyhow!("The number '{}' is too large.", s))
+}
+
+/// Format a number of bytes for humans, with SI prefixes and up to one decimal place, e.g.
+/// `999 B`, `1.5 kB` or `10 GB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["kB", "MB", "GB", "TB", "PB", "EB"];
+
+    if bytes < KILO {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next_unit in UNITS {
+        if value < 999.95 {
+            break;
+        }
+        value /= KILO as f64;
+        unit = next_unit;
+    }
+
+    let formatted = format!("{:.1}", value);
+    let formatted = formatted.strip_suffix(".0").unwrap_or(&formatted);
+    format!("{} {}", formatted, unit)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,7 +180,7 @@ mod tests {
                 #[test]
                 fn $name() {
                     let (txt, expected) = $val;
-                    let actual = SizeFilter::from_string(txt).unwrap();
+                    let actual = SizeFilter::parse(txt).unwrap();
                     assert_eq!(actual, expected);
                 }
             )*
@@ -157,6 +260,13 @@ mod tests {
         tebi_plus_suffix_upper:   ("+1TiB",   SizeFilter::Min(1_099_511_627_776)),
         tebi_minus_upper:         ("-1Ti",    SizeFilter::Max(1_099_511_627_776)),
         tebi_minus_suffix_upper:  ("-1TIB",   SizeFilter::Max(1_099_511_627_776)),
+        fraction_plus:            ("+1.5k",   SizeFilter::Min(1500)),
+        fraction_minus:           ("-1.5gi",  SizeFilter::Max(1_610_612_736)),
+        fraction_equals:          ("0.25mb",  SizeFilter::Equals(250_000)),
+        fraction_truncated:       ("1.5b",    SizeFilter::Equals(1)),
+        range:                    ("10M..1G", SizeFilter::Range(10_000_000, 1_000_000_000)),
+        range_fraction:           ("1.5k..2ki", SizeFilter::Range(1500, 2048)),
+        range_single:             ("1k..1k",  SizeFilter::Range(1000, 1000)),
     }
 
     /// Invalid parse testing
@@ -165,8 +275,8 @@ mod tests {
             $(
                 #[test]
                 fn $name() {
-                    let i = SizeFilter::from_string($value);
-                    assert!(i.is_none());
+                    let i = SizeFilter::parse($value);
+                    assert!(i.is_err());
                 }
             )*
         };
@@ -184,29 +294,102 @@ mod tests {
         ensure_invalid_unit_returns_none_3: "+1Mv",
         ensure_bib_format_returns_none: "+1bib",
         ensure_bb_format_returns_none: "+1bb",
+        ensure_missing_fraction_returns_none: "+1.k",
+        ensure_missing_integer_returns_none: "+.5k",
+        ensure_signed_range_returns_none: "+1k..2k",
+        ensure_open_range_returns_none: "1k..",
+        ensure_inverted_range_returns_none: "1G..10M",
+        ensure_overflow_returns_none: "+20000000ti",
     }
 
     #[test]
     fn is_within_less_than() {
-        let f = SizeFilter::from_string("-1k").unwrap();
+        let f = SizeFilter::parse("-1k").unwrap();
         assert!(f.is_within(999));
     }
 
     #[test]
     fn is_within_less_than_equal() {
-        let f = SizeFilter::from_string("-1k").unwrap();
+        let f = SizeFilter::parse("-1k").unwrap();
         assert!(f.is_within(1000));
     }
 
     #[test]
     fn is_within_greater_than() {
-        let f = SizeFilter::from_string("+1k").unwrap();
+        let f = SizeFilter::parse("+1k").unwrap();
         assert!(f.is_within(1001));
     }
 
     #[test]
     fn is_within_greater_than_equal() {
-        let f = SizeFilter::from_string("+1K").unwrap();
+        let f = SizeFilter::parse("+1K").unwrap();
+        assert!(f.is_within(1000));
+    }
+
+    #[test]
+    fn is_within_range() {
+        let f = SizeFilter::parse("1k..2k").unwrap();
+        assert!(!f.is_within(999));
         assert!(f.is_within(1000));
+        assert!(f.is_within(2000));
+        assert!(!f.is_within(2001));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |s| SizeFilter::parse(s).unwrap_err().to_string();
+        assert_eq!(
+            error("+18"),
+            "The size '18' has no unit. Use e.g. '18b' for bytes or '18k' for kilobytes."
+        );
+        assert!(error("1.5X").starts_with("'1.5X' is not a valid size."));
+        assert_eq!(
+            error("1G..10M"),
+            "Invalid range '1G..10M': the lower bound (1 GB) is larger than the upper bound \
+             (10 MB)."
+        );
+        assert_eq!(
+            error("+99999999999999999999999999999999999ti"),
+            "The size '99999999999999999999999999999999999ti' is too large."
+        );
+        assert_eq!(
+            error("-99999999999999999999999999999999999.5ti"),
+            "The size '99999999999999999999999999999999999.5ti' is too large."
+        );
+        assert_eq!(
+            error("+16777216.000000000000001ti"),
+            "The size '16777216.000000000000001ti' is too large."
+        );
+    }
+
+    #[test]
+    fn parse_counts() {
+        assert_eq!(
+            SizeFilter::parse_count("+100").unwrap(),
+            SizeFilter::Min(100)
+        );
+        assert_eq!(SizeFilter::parse_count("-5").unwrap(), SizeFilter::Max(5));
+        assert_eq!(SizeFilter::parse_count("0").unwrap(), SizeFilter::Equals(0));
+        assert_eq!(
+            SizeFilter::parse_count("10..20").unwrap(),
+            SizeFilter::Range(10, 20)
+        );
+
+        assert!(SizeFilter::parse_count("+1k").is_err());
+        assert!(SizeFilter::parse_count("+1.5").is_err());
+        assert!(SizeFilter::parse_count("+").is_err());
+        assert!(SizeFilter::parse_count("20..10").is_err());
+    }
+
+    #[test]
+    fn format_sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(999), "999 B");
+        assert_eq!(format_size(1000), "1 kB");
+        assert_eq!(format_size(1500), "1.5 kB");
+        assert_eq!(format_size(999_949), "999.9 kB");
+        assert_eq!(format_size(999_950), "1 MB");
+        assert_eq!(format_size(4 * 1024 * 1024 * 1024), "4.3 GB");
+        assert_eq!(format_size(u64::MAX), "18.4 EB");
     }
 }
@@ -0,0 +1,80 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::bytes::Regex;
+
+use crate::filesystem;
+
+/// Filter for symbolic links (`--broken-symlinks`, `--symlink-target`, `--symlink-escapes`).
+/// Only symbolic links that pass all of the given checks are shown.
+pub struct SymlinkFilter {
+    pub broken_only: bool,
+    pub target: Option<Regex>,
+    pub escapes_only: bool,
+    /// The search paths, which links must not point out of with `--symlink-escapes`.
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl SymlinkFilter {
+    /// Check the symbolic link at `path`. Anything that is not a symbolic link does not match.
+    pub fn matches(&self, path: &Path) -> bool {
+        let is_symlink = path
+            .symlink_metadata()
+            .map_or(false, |m| m.file_type().is_symlink());
+        if !is_symlink {
+            return false;
+        }
+        let target = match fs::read_link(path) {
+            Ok(target) => target,
+            Err(_) => return false,
+        };
+
+        if self.broken_only && path.metadata().is_ok() {
+            return false;
+        }
+
+        if let Some(ref regex) = self.target {
+            if !regex.is_match(&filesystem::osstr_to_bytes(target.as_os_str())) {
+                return false;
+            }
+        }
+
+        if self.escapes_only {
+            // Paths that are not below a search path come from '--paths-from', and are relative
+            // to the current directory.
+            let root = filesystem::search_root_of(path, &self.search_paths)
+                .unwrap_or_else(|| Path::new(""));
+            match (resolve_link(path, &target), canonical_root(root)) {
+                (Some(resolved), Some(root)) if !resolved.starts_with(&root) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+fn canonical_root(root: &Path) -> Option<PathBuf> {
+    let root = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root
+    };
+    fs::canonicalize(root).ok()
+}
+
+/// The absolute path that a symbolic link points to. For broken links, this is resolved as far as
+/// possible: the directory that contains the link is canonicalized, the rest is resolved
+/// lexically.
+fn resolve_link(path: &Path, target: &Path) -> Option<PathBuf> {
+    if let Ok(resolved) = fs::canonicalize(path) {
+        return Some(resolved);
+    }
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = fs::canonicalize(parent).ok()?;
+    Some(filesystem::normalize_lexically(&parent.join(target)))
+}
@@ -0,0 +1,135 @@
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+/// The extended attributes in which Linux stores POSIX ACLs. They only exist if the ACL contains
+/// more than what the mode bits express.
+const ACL_ATTRIBUTES: &[&str] = &["system.posix_acl_access", "system.posix_acl_default"];
+
+#[derive(Clone, Debug, PartialEq)]
+struct Check {
+    name: OsString,
+    value: Option<Vec<u8>>,
+    negated: bool,
+}
+
+impl Check {
+    /// Parses `NAME`, `NAME=VALUE` or `!NAME`.
+    fn parse(input: &str) -> Result<Check> {
+        let (negated, attribute) = match input.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, input),
+        };
+        let (name, value) = match attribute.find('=') {
+            Some(index) => (
+                &attribute[..index],
+                Some(attribute.as_bytes()[index + 1..].to_vec()),
+            ),
+            None => (attribute, None),
+        };
+
+        if name.is_empty() {
+            return Err(anyhow!(
+                "'{}' is not a valid extended attribute. Use 'NAME', 'NAME=VALUE' or '!NAME'.",
+                input
+            ));
+        }
+        Ok(Check {
+            name: OsString::from(name),
+            value,
+            negated,
+        })
+    }
+}
+
+/// Filter for extended attributes (`--xattr`) and POSIX ACLs (`--has-acl`).
+pub struct XattrFilter {
+    checks: Vec<Check>,
+    has_acl: bool,
+    follow_links: bool,
+}
+
+impl XattrFilter {
+    pub fn new<'a, I: Iterator<Item = &'a str>>(
+        attributes: I,
+        has_acl: bool,
+        follow_links: bool,
+    ) -> Result<Option<XattrFilter>> {
+        let checks = attributes.map(Check::parse).collect::<Result<Vec<_>>>()?;
+        if checks.is_empty() && !has_acl {
+            return Ok(None);
+        }
+        Ok(Some(XattrFilter {
+            checks,
+            has_acl,
+            follow_links,
+        }))
+    }
+
+    /// The value of an attribute. File systems without support for extended attributes are
+    /// treated as if the attribute was missing.
+    fn get(&self, path: &Path, name: &OsStr) -> Option<Vec<u8>> {
+        let value: io::Result<Option<Vec<u8>>> = if self.follow_links {
+            xattr::get_deref(path, name)
+        } else {
+            xattr::get(path, name)
+        };
+        value.ok().flatten()
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        let checks_pass = self.checks.iter().all(|check| {
+            let value = self.get(path, &check.name);
+            let found = match check.value {
+                Some(ref expected) => value.as_ref() == Some(expected),
+                None => value.is_some(),
+            };
+            found != check.negated
+        });
+
+        checks_pass
+            && (!self.has_acl
+                || ACL_ATTRIBUTES
+                    .iter()
+                    .any(|name| self.get(path, OsStr::new(name)).is_some()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_checks() {
+        assert_eq!(
+            Check::parse("user.checksum").unwrap(),
+            Check {
+                name: OsString::from("user.checksum"),
+                value: None,
+                negated: false,
+            }
+        );
+        assert_eq!(
+            Check::parse("!user.processed").unwrap(),
+            Check {
+                name: OsString::from("user.processed"),
+                value: None,
+                negated: true,
+            }
+        );
+        assert_eq!(
+            Check::parse("security.selinux=system_u:object_r:etc_t:s0").unwrap(),
+            Check {
+                name: OsString::from("security.selinux"),
+                value: Some(b"system_u:object_r:etc_t:s0".to_vec()),
+                negated: false,
+            }
+        );
+
+        assert!(Check::parse("").is_err());
+        assert!(Check::parse("!").is_err());
+        assert!(Check::parse("=value").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+
+use crate::exec::{basename, dirname, remove_extension};
+use crate::filesystem::strip_current_dir;
+use crate::filter::format_size;
+
+/// A single piece of an output template.
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Placeholder,
+    Basename,
+    Parent,
+    NoExt,
+    BasenameNoExt,
+    Root,
+    Pattern,
+    Target,
+    Size,
+}
+
+/// Describes how a search result is printed (`--format`).
+///
+/// The template understands the same placeholders as `--exec`. In addition, `{root}` refers to
+/// the search path that the result was found in, `{pattern}` to the search pattern that
+/// matched it, `{target}` to the target of a symbolic link and `{size}` to the size of a file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatTemplate {
+    parts: Vec<Part>,
+}
+
+impl FormatTemplate {
+    pub fn new(template: &str) -> Result<FormatTemplate> {
+        lazy_static! {
+            static ref PLACEHOLDER_PATTERN: regex::Regex =
+                regex::Regex::new(r"\{(/?\.?|//|[[:alpha:]_][[:word:]]*)\}").unwrap();
+        }
+
+        let mut parts = Vec::new();
+        let mut start = 0;
+
+        for placeholder in PLACEHOLDER_PATTERN.captures_iter(template) {
+            let whole = placeholder.get(0).unwrap();
+
+            // Leading text before the placeholder.
+            if whole.start() > start {
+                parts.push(Part::Text(template[start..whole.start()].to_owned()));
+            }
+
+            start = whole.end();
+
+            let part = match &placeholder[1] {
+                "" => Part::Placeholder,
+                "." => Part::NoExt,
+                "/" => Part::Basename,
+                "//" => Part::Parent,
+                "/." => Part::BasenameNoExt,
+                "root" => Part::Root,
+                "pattern" => Part::Pattern,
+                "target" => Part::Target,
+                "size" => Part::Size,
+                name => {
+                    return Err(anyhow!(
+                        "Unknown placeholder '{{{}}}' in the format template.",
+                        name
+                    ))
+                }
+            };
+            parts.push(part);
+        }
+
+        // Trailing text after the last placeholder.
+        if start < template.len() {
+            parts.push(Part::Text(template[start..].to_owned()));
+        }
+
+        Ok(FormatTemplate { parts })
+    }
+
+    /// Whether the template refers to the search pattern that matched a result.
+    pub fn uses_pattern(&self) -> bool {
+        self.parts.contains(&Part::Pattern)
+    }
+
+    /// Render the template for a search result that was found in the search path `root` and
+    /// matched by `pattern`.
+    pub fn generate(&self, path: &Path, root: Option<&Path>, pattern: Option<&str>) -> OsString {
+        let mut s = OsString::new();
+        for part in &self.parts {
+            match *part {
+                Part::Text(ref text) => s.push(text),
+                Part::Placeholder => s.push(path),
+                Part::Basename => s.push(basename(path)),
+                Part::Parent => s.push(dirname(path)),
+                Part::NoExt => s.push(remove_extension(path)),
+                Part::BasenameNoExt => s.push(remove_extension(Path::new(basename(path)))),
+                Part::Root => {
+                    if let Some(root) = root {
+                        // Keep the current directory itself as '.', but strip it from './foo'.
+                        let stripped = strip_current_dir(root);
+                        if stripped.as_os_str().is_empty() {
+                            s.push(root);
+                        } else {
+                            s.push(stripped);
+                        }
+                    }
+                }
+                Part::Pattern => {
+                    if let Some(pattern) = pattern {
+                        s.push(pattern);
+                    }
+                }
+                Part::Target => {
+                    if let Ok(target) = fs::read_link(path) {
+                        s.push(target);
+                    }
+                }
+                Part::Size => {
+                    if let Ok(metadata) = fs::metadata(path) {
+                        if metadata.is_file() {
+                            s.push(format_size(metadata.len()));
+                        }
+                    }
+                }
+            }
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FormatTemplate;
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    #[test]
+    fn placeholders() {
+        let template = FormatTemplate::new("{root}: {/.} in {//}").unwrap();
+        assert_eq!(
+            template.generate(Path::new("src/exec/mod.rs"), Some(Path::new("src")), None),
+            OsString::from("src: mod in src/exec")
+        );
+        assert_eq!(
+            template.generate(Path::new("a.txt"), None, None),
+            OsString::from(": a in .")
+        );
+
+        let template = FormatTemplate::new("{root}").unwrap();
+        assert_eq!(
+            template.generate(Path::new("./a.txt"), Some(Path::new(".")), None),
+            OsString::from(".")
+        );
+        assert_eq!(
+            template.generate(Path::new("./src/a.txt"), Some(Path::new("./src")), None),
+            OsString::from("src")
+        );
+    }
+
+    #[test]
+    fn unknown_placeholder() {
+        assert!(FormatTemplate::new("{}").is_ok());
+        assert!(FormatTemplate::new("{bytes}").is_err());
+
+        // Entries that are not symbolic links have no target
+        let template = FormatTemplate::new("{} -> {target}").unwrap();
+        assert_eq!(
+            template.generate(Path::new("does-not-exist"), None, None),
+            OsString::from("does-not-exist -> ")
+        );
+
+        let template = FormatTemplate::new("{pattern}: {}").unwrap();
+        assert!(template.uses_pattern());
+        assert_eq!(
+            template.generate(Path::new("id_rsa"), None, Some("^id_")),
+            OsString::from("^id_: id_rsa")
+        );
+    }
+
+    #[test]
+    fn size() {
+        // Only files have a size
+        let template = FormatTemplate::new("{/}: {size}").unwrap();
+        assert_eq!(
+            template.generate(Path::new("src"), None, None),
+            OsString::from("src: ")
+        );
+        assert_eq!(
+            template.generate(Path::new("does-not-exist"), None, None),
+            OsString::from("does-not-exist: ")
+        );
+    }
+}
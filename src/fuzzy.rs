@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use crate::filesystem;
+use crate::normalize::NameNormalizer;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+
+/// Bonus for a match at the start of a word, e.g. after a path separator or an underscore.
+const BONUS_BOUNDARY: i64 = 8;
+/// Bonus for a match at an uppercase character that follows a lowercase one (camelCase).
+const BONUS_CAMEL_CASE: i64 = 7;
+/// Bonus for every character that directly follows the previous match.
+const BONUS_CONSECUTIVE: i64 = 4;
+/// The bonus of the first character of the query is multiplied by this factor.
+const BONUS_FIRST_CHAR_MULTIPLIER: i64 = 2;
+
+const NO_MATCH: i64 = i64::MIN / 2;
+
+fn is_word_separator(c: char) -> bool {
+    matches!(c, '/' | '\\' | '_' | '-' | '.' | ' ')
+}
+
+/// The bonus for matching the character at `index` of the candidate.
+fn position_bonus(candidate: &[char], index: usize) -> i64 {
+    if index == 0 {
+        return BONUS_BOUNDARY;
+    }
+
+    let (previous, current) = (candidate[index - 1], candidate[index]);
+    if is_word_separator(previous) {
+        BONUS_BOUNDARY
+    } else if previous.is_lowercase() && current.is_uppercase()
+        || !previous.is_numeric() && current.is_numeric()
+    {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+/// Scores file names or paths against a query (`--fuzzy`). A candidate matches if it contains all
+/// characters of the query in the same order. Matches at the start of words and consecutive
+/// matches are preferred, gaps between the matched characters are penalized.
+#[derive(Clone, Debug)]
+pub struct FuzzyMatcher {
+    query: Vec<char>,
+    case_sensitive: bool,
+}
+
+impl FuzzyMatcher {
+    pub fn new(query: &str, case_sensitive: bool) -> FuzzyMatcher {
+        let query = if case_sensitive {
+            query.chars().collect()
+        } else {
+            query.chars().flat_map(char::to_lowercase).collect()
+        };
+        FuzzyMatcher {
+            query,
+            case_sensitive,
+        }
+    }
+
+    /// The score of the best alignment of the query in `candidate`, or `None` if the candidate
+    /// does not contain the query as a subsequence.
+    pub fn score(&self, candidate: &str) -> Option<i64> {
+        if self.query.is_empty() {
+            return Some(0);
+        }
+
+        let original: Vec<char> = candidate.chars().collect();
+        let folded: Vec<char> = if self.case_sensitive {
+            original.clone()
+        } else {
+            // Only fold characters that stay a single character, to keep the indices aligned.
+            original
+                .iter()
+                .map(|&c| {
+                    let mut lower = c.to_lowercase();
+                    match (lower.next(), lower.next()) {
+                        (Some(l), None) => l,
+                        _ => c,
+                    }
+                })
+                .collect()
+        };
+
+        let (n, m) = (self.query.len(), folded.len());
+        if n > m {
+            return None;
+        }
+
+        // `previous[j]` is the best score of the query prefix of the last row, with its last
+        // character matched at position `j` of the candidate.
+        let mut previous = vec![NO_MATCH; m];
+        let mut current = vec![NO_MATCH; m];
+        for (i, &q) in self.query.iter().enumerate() {
+            // The best score of the previous row that ends at least two characters before `j`,
+            // including the penalty for the gap.
+            let mut gap_best = NO_MATCH;
+            for j in 0..m {
+                if j >= 2 {
+                    gap_best =
+                        (gap_best + SCORE_GAP_EXTENSION).max(previous[j - 2] + SCORE_GAP_START);
+                }
+
+                current[j] = if folded[j] != q {
+                    NO_MATCH
+                } else if i == 0 {
+                    SCORE_MATCH + position_bonus(&original, j) * BONUS_FIRST_CHAR_MULTIPLIER
+                } else if j == 0 {
+                    NO_MATCH
+                } else {
+                    let consecutive = previous[j - 1] + BONUS_CONSECUTIVE;
+                    let best = consecutive.max(gap_best);
+                    if best <= NO_MATCH / 2 {
+                        NO_MATCH
+                    } else {
+                        best + SCORE_MATCH + position_bonus(&original, j)
+                    }
+                };
+            }
+            std::mem::swap(&mut previous, &mut current);
+        }
+
+        previous.into_iter().filter(|&s| s > NO_MATCH / 2).max()
+    }
+
+    /// Score the file name of `path`, or the full path if `full_path` is set.
+    pub fn score_path(
+        &self,
+        path: &Path,
+        full_path: bool,
+        normalizer: Option<&NameNormalizer>,
+    ) -> Option<i64> {
+        let candidate = if full_path {
+            filesystem::path_absolute_form(path).ok()?.into_os_string()
+        } else {
+            path.file_name()?.to_os_string()
+        };
+        match normalizer {
+            Some(normalizer) => self.score(&normalizer.normalize(&candidate.to_string_lossy())),
+            None => self.score(&candidate.to_string_lossy()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FuzzyMatcher;
+
+    fn score(query: &str, candidate: &str) -> Option<i64> {
+        FuzzyMatcher::new(query, false).score(candidate)
+    }
+
+    #[test]
+    fn subsequences_match() {
+        assert!(score("mnrs", "main.rs").is_some());
+        assert!(score("", "main.rs").is_some());
+        assert!(score("rsm", "main.rs").is_none());
+        assert!(score("mainrs", "main.r").is_none());
+    }
+
+    #[test]
+    fn case_sensitivity() {
+        assert!(score("readme", "README.md").is_some());
+        assert!(FuzzyMatcher::new("Readme", true)
+            .score("README.md")
+            .is_none());
+        assert!(FuzzyMatcher::new("README", true)
+            .score("README.md")
+            .is_some());
+    }
+
+    #[test]
+    fn ranking() {
+        // Consecutive matches beat scattered ones.
+        assert!(score("main", "main.rs") > score("main", "my_animation.rs"));
+        // Matches at word boundaries beat matches inside of words.
+        assert!(score("fb", "foo_bar") > score("fb", "fizzbuzz"));
+        assert!(score("fb", "FooBar") > score("fb", "fizzbuzz"));
+        // Shorter gaps are better.
+        assert!(score("ab", "axb") > score("ab", "axxxxb"));
+    }
+}
@@ -1,16 +1,25 @@
+mod action;
 mod app;
+mod archive;
 mod config;
 mod error;
 mod exec;
 mod exit_codes;
+mod explain;
 mod filesystem;
 mod filetypes;
 mod filter;
+mod format;
+mod fuzzy;
+mod normalize;
 mod output;
 mod regex_helper;
 mod walk;
 
 use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::Arc;
@@ -23,15 +32,24 @@ use lscolors::LsColors;
 use normpath::PathExt;
 use regex::bytes::{RegexBuilder, RegexSetBuilder};
 
+use crate::action::{RenameTemplate, TransferMode};
+use crate::archive::ArchiveFormat;
 use crate::config::Config;
 use crate::error::print_error;
 use crate::exec::CommandTemplate;
 use crate::exit_codes::ExitCode;
 use crate::filetypes::FileTypes;
+use crate::filter::{
+    format_size, ContentFilter, DirectoryFilter, Exclusion, GitFilter, KindDefinitions, SizeFilter,
+    SymlinkFilter, TimeFilter,
+};
 #[cfg(unix)]
-use crate::filter::OwnerFilter;
-use crate::filter::{SizeFilter, TimeFilter};
+use crate::filter::{OwnerFilter, XattrFilter};
+use crate::format::FormatTemplate;
+use crate::fuzzy::FuzzyMatcher;
+use crate::normalize::NameNormalizer;
 use crate::regex_helper::{pattern_has_uppercase_char, pattern_matches_strings_with_leading_dot};
+use crate::walk::{DirectoryOrder, Traversal};
 
 // We use jemalloc for performance reasons, see https://github.com/sharkdp/fd/pull/481
 // FIXME: re-enable jemalloc on macOS, see comment in Cargo.toml file for more infos
@@ -66,21 +84,89 @@ fn main() {
 fn run() -> Result<ExitCode> {
     let matches = app::build_app().get_matches_from(env::args_os());
 
+    if matches.is_present("kind-list") {
+        return print_kind_list(&matches);
+    }
+
     set_working_dir(&matches)?;
     let current_directory = Path::new(".");
     ensure_current_directory_exists(current_directory)?;
     let search_paths = extract_search_paths(&matches, current_directory)?;
 
-    let pattern = extract_search_pattern(&matches)?;
-    ensure_search_pattern_is_not_a_path(&matches, pattern)?;
-    let pattern_regex = build_pattern_regex(&matches, pattern)?;
+    let patterns = extract_search_patterns(&matches)?;
+    let normalizer = NameNormalizer::new(
+        matches.value_of("normalize"),
+        matches.is_present("case-fold"),
+        matches.is_present("ignore-diacritics"),
+    )?;
+    let mut pattern_regexes = Vec::with_capacity(patterns.len());
+    for pattern in &patterns {
+        ensure_search_pattern_is_not_a_path(&matches, pattern)?;
+        pattern_regexes.push(match normalizer {
+            Some(ref normalizer)
+                if matches.is_present("glob") || matches.is_present("path-glob") =>
+            {
+                build_pattern_regex(&matches, &normalizer.normalize_glob(pattern))?
+            }
+            Some(ref normalizer) => {
+                normalizer.normalize_regex(&build_pattern_regex(&matches, pattern)?)
+            }
+            None => build_pattern_regex(&matches, pattern)?,
+        });
+    }
+    let pattern_regex = combine_pattern_regexes(&pattern_regexes);
 
-    let config = construct_config(matches, &pattern_regex)?;
+    let mut config = construct_config(&matches, &pattern_regex, &search_paths)?;
     ensure_use_hidden_option_for_leading_dot_pattern(&config, &pattern_regex)?;
     let re = build_regex(pattern_regex, &config)?;
+    config.rename = extract_rename_template(&matches, &re)?;
+    if matches.is_present("fuzzy") {
+        let query = match normalizer {
+            Some(ref normalizer) => normalizer.normalize(&patterns[0]),
+            None => patterns[0].clone(),
+        };
+        config.fuzzy = Some(FuzzyMatcher::new(&query, config.case_sensitive));
+    }
+    config.normalizer = normalizer;
+    let pattern_set = RegexSetBuilder::new(&pattern_regexes)
+        .case_insensitive(!config.case_sensitive)
+        .dot_matches_new_line(true)
+        .build()?;
+    config.search_patterns = Some((pattern_set, patterns));
+    if let Some(path) = matches.value_of_os("explain") {
+        return explain::explain(Path::new(path), &search_paths, &re, &config);
+    }
+    if let Some(source) = matches.value_of_os("paths-from") {
+        let mut paths = read_path_list(source)?;
+        if matches.is_present("absolute-path") {
+            for path in paths.iter_mut() {
+                *path = filesystem::absolute_path(path)?;
+            }
+        }
+        return walk::filter_paths(paths, Arc::new(re), Arc::new(config));
+    }
     walk::scan(&search_paths, Arc::new(re), Arc::new(config))
 }
 
+/// The predefined kinds together with the ones from `--kind-add`.
+fn extract_kind_definitions(matches: &clap::ArgMatches) -> Result<KindDefinitions> {
+    let mut definitions = KindDefinitions::predefined();
+    for definition in matches.values_of("kind-add").into_iter().flatten() {
+        definitions.add(definition)?;
+    }
+    Ok(definitions)
+}
+
+fn print_kind_list(matches: &clap::ArgMatches) -> Result<ExitCode> {
+    let definitions = extract_kind_definitions(matches)?;
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in definitions.list() {
+        writeln!(stdout, "{}", line)?;
+    }
+    Ok(ExitCode::Success)
+}
+
 fn set_working_dir(matches: &clap::ArgMatches) -> Result<()> {
     if let Some(base_directory) = matches.value_of_os("base-directory") {
         let base_directory = Path::new(base_directory);
@@ -110,44 +196,110 @@ fn ensure_current_directory_exists(current_directory: &Path) -> Result<()> {
     }
 }
 
-fn extract_search_pattern<'a>(matches: &'a clap::ArgMatches) -> Result<&'a str> {
-    let pattern = matches
-        .value_of_os("pattern")
-        .map(|p| {
-            p.to_str()
-                .ok_or_else(|| anyhow!("The search pattern includes invalid UTF-8 sequences."))
-        })
-        .transpose()?
-        .unwrap_or("");
-    Ok(pattern)
+/// Whether the search patterns are given by options, in which case all positional arguments are
+/// search paths.
+fn uses_pattern_options(matches: &clap::ArgMatches) -> bool {
+    matches.is_present("patterns") || matches.is_present("patterns-from")
+}
+
+fn extract_search_patterns(matches: &clap::ArgMatches) -> Result<Vec<String>> {
+    if !uses_pattern_options(matches) {
+        let pattern = matches
+            .value_of_os("pattern")
+            .map(|p| {
+                p.to_str()
+                    .ok_or_else(|| anyhow!("The search pattern includes invalid UTF-8 sequences."))
+            })
+            .transpose()?
+            .unwrap_or("");
+        return Ok(vec![pattern.to_owned()]);
+    }
+
+    let mut patterns: Vec<String> = matches
+        .values_of("patterns")
+        .map(|v| v.map(String::from).collect())
+        .unwrap_or_default();
+    if let Some(source) = matches.value_of_os("patterns-from") {
+        let input = fs::read_to_string(source).with_context(|| {
+            format!(
+                "Could not read the search patterns from '{}'",
+                Path::new(source).to_string_lossy()
+            )
+        })?;
+        patterns.extend(
+            input
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(String::from),
+        );
+    }
+
+    if patterns.is_empty() {
+        return Err(anyhow!("No search patterns given."));
+    }
+    Ok(patterns)
+}
+
+/// Combine the search patterns into a single regex that matches if any of them matches.
+fn combine_pattern_regexes(pattern_regexes: &[String]) -> String {
+    if let [pattern_regex] = pattern_regexes {
+        return pattern_regex.clone();
+    }
+    pattern_regexes
+        .iter()
+        .map(|p| format!("(?:{})", p))
+        .collect::<Vec<_>>()
+        .join("|")
 }
 
 fn extract_search_paths(
     matches: &clap::ArgMatches,
     current_directory: &Path,
 ) -> Result<Vec<PathBuf>> {
-    let mut search_paths = matches
-        .values_of_os("path")
-        .or_else(|| matches.values_of_os("search-path"))
-        .map_or_else(
-            || vec![current_directory.to_path_buf()],
-            |paths| {
-                paths
-                    .filter_map(|path| {
-                        let path_buffer = PathBuf::from(path);
-                        if filesystem::is_existing_directory(&path_buffer) {
-                            Some(path_buffer)
-                        } else {
-                            print_error(format!(
-                                "Search path '{}' is not a directory.",
-                                path_buffer.to_string_lossy()
-                            ));
-                            None
-                        }
-                    })
-                    .collect()
-            },
-        );
+    let paths: Option<Vec<PathBuf>> = match matches.value_of_os("roots-from") {
+        Some(source) => Some(read_path_list(source)?),
+        None => {
+            // The first positional argument is a search path if the patterns are given by options.
+            let pattern_path = matches
+                .value_of_os("pattern")
+                .filter(|_| uses_pattern_options(matches));
+            let paths: Vec<PathBuf> = pattern_path
+                .into_iter()
+                .chain(
+                    matches
+                        .values_of_os("path")
+                        .or_else(|| matches.values_of_os("search-path"))
+                        .into_iter()
+                        .flatten(),
+                )
+                .map(PathBuf::from)
+                .collect();
+            if paths.is_empty() {
+                None
+            } else {
+                Some(paths)
+            }
+        }
+    };
+    let mut search_paths = paths.map_or_else(
+        || vec![current_directory.to_path_buf()],
+        |paths| {
+            paths
+                .into_iter()
+                .filter_map(|path_buffer| {
+                    if filesystem::is_existing_directory(&path_buffer) {
+                        Some(path_buffer)
+                    } else {
+                        print_error(format!(
+                            "Search path '{}' is not a directory.",
+                            path_buffer.to_string_lossy()
+                        ));
+                        None
+                    }
+                })
+                .collect()
+        },
+    );
     if search_paths.is_empty() {
         return Err(anyhow!("No valid search paths given."));
     }
@@ -157,6 +309,39 @@ fn extract_search_paths(
     Ok(search_paths)
 }
 
+/// Read a list of paths from a file, or from stdin if `source` is `-`. The paths are separated by
+/// null characters if there are any, and by newlines otherwise.
+fn read_path_list(source: &OsStr) -> Result<Vec<PathBuf>> {
+    let mut input = Vec::new();
+    if source == "-" {
+        io::stdin()
+            .lock()
+            .read_to_end(&mut input)
+            .context("Could not read the list of paths from standard input")?;
+    } else {
+        input = fs::read(source).with_context(|| {
+            format!(
+                "Could not read the list of paths from '{}'",
+                Path::new(source).to_string_lossy()
+            )
+        })?;
+    }
+
+    let separator = if input.contains(&0) { 0 } else { b'\n' };
+    Ok(input
+        .split(|b| *b == separator)
+        .map(|path| {
+            if separator == b'\n' {
+                path.strip_suffix(b"\r").unwrap_or(path)
+            } else {
+                path
+            }
+        })
+        .filter(|path| !path.is_empty())
+        .map(|path| PathBuf::from(filesystem::osstr_from_bytes(path).into_owned()))
+        .collect())
+}
+
 fn update_to_absolute_paths(search_paths: &mut [PathBuf]) {
     for buffer in search_paths.iter_mut() {
         *buffer = filesystem::absolute_path(buffer.normalize().unwrap().as_path()).unwrap();
@@ -185,11 +370,23 @@ fn ensure_search_pattern_is_not_a_path(matches: &clap::ArgMatches, pattern: &str
 }
 
 fn build_pattern_regex(matches: &clap::ArgMatches, pattern: &str) -> Result<String> {
-    Ok(if matches.is_present("glob") && !pattern.is_empty() {
+    Ok(if matches.is_present("path-glob") && !pattern.is_empty() {
+        // Like in '.gitignore' files, a pattern without a separator matches at any depth and a
+        // leading separator only anchors the pattern.
+        let anchored = pattern.trim_end_matches('/');
+        let pattern = if anchored.contains('/') {
+            anchored.trim_start_matches('/').to_owned()
+        } else {
+            format!("**/{}", anchored)
+        };
+        let glob = GlobBuilder::new(&pattern).literal_separator(true).build()?;
+        glob.regex().to_owned()
+    } else if matches.is_present("glob") && !pattern.is_empty() {
         let glob = GlobBuilder::new(pattern).literal_separator(true).build()?;
         glob.regex().to_owned()
-    } else if matches.is_present("fixed-strings") {
-        // Treat pattern as literal string if '--fixed-strings' is used
+    } else if matches.is_present("fixed-strings") || matches.is_present("fuzzy") {
+        // Treat pattern as literal string if '--fixed-strings' is used. A fuzzy query is matched
+        // by its own scorer, but the literal regex is used to determine the case sensitivity.
         regex::escape(pattern)
     } else {
         String::from(pattern)
@@ -210,10 +407,15 @@ fn check_path_separator_length(path_separator: Option<&str>) -> Result<()> {
     }
 }
 
-fn construct_config(matches: clap::ArgMatches, pattern_regex: &str) -> Result<Config> {
+fn construct_config(
+    matches: &clap::ArgMatches,
+    pattern_regex: &str,
+    search_paths: &[PathBuf],
+) -> Result<Config> {
     // The search will be case-sensitive if the command line flag is set or
     // if the pattern has an uppercase character (smart case).
     let case_sensitive = !matches.is_present("ignore-case")
+        && !matches.is_present("case-fold")
         && (matches.is_present("case-sensitive") || pattern_has_uppercase_char(pattern_regex));
 
     let path_separator = matches
@@ -221,8 +423,94 @@ fn construct_config(matches: clap::ArgMatches, pattern_regex: &str) -> Result<Co
         .map_or_else(filesystem::default_path_separator, |s| Some(s.to_owned()));
     check_path_separator_length(path_separator.as_deref())?;
 
-    let size_limits = extract_size_limits(&matches)?;
-    let time_constraints = extract_time_constraints(&matches)?;
+    let size_limits = extract_size_limits(matches)?;
+
+    let directory_order = if matches.is_present("dirs-first") {
+        Some(DirectoryOrder::First)
+    } else if matches.is_present("dirs-last") {
+        Some(DirectoryOrder::Last)
+    } else {
+        None
+    };
+    // Directories can only be ordered by a single-threaded traversal, depth-first by default.
+    let traversal = match matches.value_of("traversal") {
+        Some("bfs") => Some(Traversal::BreadthFirst),
+        Some(_) => Some(Traversal::DepthFirst),
+        None if directory_order.is_some() => Some(Traversal::DepthFirst),
+        None => None,
+    };
+    let dir_filter = DirectoryFilter::new(
+        matches
+            .values_of("dir-entries")
+            .into_iter()
+            .flatten()
+            .map(SizeFilter::parse_count)
+            .collect::<Result<_>>()?,
+        matches
+            .values_of("dir-size")
+            .into_iter()
+            .flatten()
+            .map(SizeFilter::parse)
+            .collect::<Result<_>>()?,
+        matches.is_present("disk-usage"),
+        matches.is_present("one-file-system"),
+    );
+    let time_constraints = extract_time_constraints(matches)?;
+    let git_filter = if matches.is_present("git-ignored-only") {
+        Some(GitFilter::ignored_only())
+    } else {
+        matches
+            .values_of("git-status")
+            .map(GitFilter::from_statuses)
+            .transpose()?
+    };
+    let symlink_target = matches
+        .value_of("symlink-target")
+        .map(|pattern| build_option_regex(matches, "--symlink-target", pattern))
+        .transpose()?;
+    let symlink_filter = if matches.is_present("broken-symlinks")
+        || matches.is_present("symlink-escapes")
+        || symlink_target.is_some()
+    {
+        Some(SymlinkFilter {
+            broken_only: matches.is_present("broken-symlinks"),
+            target: symlink_target,
+            escapes_only: matches.is_present("symlink-escapes"),
+            search_paths: search_paths.to_vec(),
+        })
+    } else {
+        None
+    };
+    let content_filter = ContentFilter::new(matches.values_of("mime").into_iter().flatten())?;
+    let kind_filter = match matches.values_of("kind") {
+        Some(kinds) => Some(extract_kind_definitions(matches)?.filter(kinds)?),
+        None => None,
+    };
+    let archive = matches
+        .value_of_os("archive")
+        .map(|path| {
+            let path = PathBuf::from(path);
+            ArchiveFormat::from_path(&path)
+                .map(|format| (format, path.clone()))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Unknown archive format of '{}'. Use one of the extensions '.tar', \
+                         '.tar.gz', '.tgz' or '.zip'.",
+                        path.to_string_lossy()
+                    )
+                })
+        })
+        .transpose()?;
+    let mut exclusions = Vec::new();
+    for pattern in matches.values_of("exclude-regex").into_iter().flatten() {
+        let regex = build_option_regex(matches, "--exclude-regex", pattern)?;
+        exclusions.push(Exclusion::regex(pattern, regex));
+    }
+    let now = time::SystemTime::now();
+    for expression in matches.values_of("exclude-if").into_iter().flatten() {
+        exclusions.push(Exclusion::condition(expression, &now)?);
+    }
+
     #[cfg(unix)]
     let owner_constraint = matches
         .value_of("owner")
@@ -230,6 +518,13 @@ fn construct_config(matches: clap::ArgMatches, pattern_regex: &str) -> Result<Co
         .transpose()?
         .flatten();
 
+    #[cfg(unix)]
+    let xattr_filter = XattrFilter::new(
+        matches.values_of("xattr").into_iter().flatten(),
+        matches.is_present("has-acl"),
+        matches.is_present("follow"),
+    )?;
+
     #[cfg(windows)]
     let ansi_colors_support =
         ansi_term::enable_ansi_support().is_ok() || std::env::var_os("TERM").is_some();
@@ -248,18 +543,28 @@ fn construct_config(matches: clap::ArgMatches, pattern_regex: &str) -> Result<Co
     } else {
         None
     };
-    let command = extract_command(&matches, path_separator.as_deref(), colored_output)?;
+    let command = extract_command(matches, path_separator.as_deref(), colored_output)?;
 
     Ok(Config {
         case_sensitive,
         search_full_path: matches.is_present("full-path"),
+        search_relative_path: matches.is_present("path-glob"),
+        parent_pattern: matches
+            .value_of("parent-pattern")
+            .map(|pattern| build_option_regex(matches, "--parent-pattern", pattern))
+            .transpose()?,
+        fuzzy: None,
+        normalizer: None,
+        // The search patterns are compiled together with the final regex, see `run`.
+        search_patterns: None,
         ignore_hidden: !(matches.is_present("hidden")
             || matches.occurrences_of("rg-alias-hidden-ignore") >= 2),
         read_fdignore: !(matches.is_present("no-ignore")
             || matches.is_present("rg-alias-hidden-ignore")),
         read_vcsignore: !(matches.is_present("no-ignore")
             || matches.is_present("rg-alias-hidden-ignore")
-            || matches.is_present("no-ignore-vcs")),
+            || matches.is_present("no-ignore-vcs")
+            || matches.is_present("git-ignored-only")),
         read_parent_ignore: !(matches.is_present("no-ignore")
             || matches.is_present("rg-alias-hidden-ignore")
             || matches.is_present("no-ignore-vcs")
@@ -302,6 +607,8 @@ fn construct_config(matches: clap::ArgMatches, pattern_regex: &str) -> Result<Co
                 .unwrap_or_else(num_cpus::get),
             1,
         ),
+        traversal,
+        directory_order,
         max_buffer_time: matches
             .value_of("max-buffer-time")
             .map(|n| n.parse::<u64>())
@@ -310,32 +617,10 @@ fn construct_config(matches: clap::ArgMatches, pattern_regex: &str) -> Result<Co
             .map(time::Duration::from_millis),
         ls_colors,
         interactive_terminal,
-        file_types: matches.values_of("file-type").map(|values| {
-            let mut file_types = FileTypes::default();
-            for value in values {
-                match value {
-                    "f" | "file" => file_types.files = true,
-                    "d" | "directory" => file_types.directories = true,
-                    "l" | "symlink" => file_types.symlinks = true,
-                    "x" | "executable" => {
-                        file_types.executables_only = true;
-                        file_types.files = true;
-                    }
-                    "e" | "empty" => file_types.empty_only = true,
-                    "s" | "socket" => file_types.sockets = true,
-                    "p" | "pipe" => file_types.pipes = true,
-                    _ => unreachable!(),
-                }
-            }
-
-            // If only 'empty' was specified, search for both files and directories:
-            if file_types.empty_only && !(file_types.files || file_types.directories) {
-                file_types.files = true;
-                file_types.directories = true;
-            }
-
-            file_types
-        }),
+        file_types: matches
+            .values_of("file-type")
+            .map(FileTypes::parse)
+            .transpose()?,
         extensions: matches
             .values_of("extension")
             .map(|exts| {
@@ -348,20 +633,62 @@ fn construct_config(matches: clap::ArgMatches, pattern_regex: &str) -> Result<Co
             })
             .transpose()?,
         command: command.map(Arc::new),
+        // The rename template refers to the capture groups of the final regex, see `run`.
+        rename: None,
+        rename_journal: matches.value_of_os("rename-journal").map(PathBuf::from),
+        delete: matches.is_present("delete"),
+        transfer: matches
+            .value_of_os("copy-to")
+            .map(|dir| (TransferMode::Copy, PathBuf::from(dir)))
+            .or_else(|| {
+                matches
+                    .value_of_os("move-to")
+                    .map(|dir| (TransferMode::Move, PathBuf::from(dir)))
+            }),
+        preserve_metadata: matches.is_present("preserve"),
+        archive,
+        git_filter,
+        symlink_filter,
+        content_filter,
+        kind_filter,
+        text_files: if matches.is_present("text-only") {
+            Some(true)
+        } else if matches.is_present("binary-only") {
+            Some(false)
+        } else {
+            None
+        },
+        search_archives: matches.is_present("search-archives"),
+        delete_recursive: matches.is_present("recursive"),
+        dry_run: matches.is_present("dry-run"),
         exclude_patterns: matches
             .values_of("exclude")
             .map(|v| v.map(|p| String::from("!") + p).collect())
-            .unwrap_or_else(Vec::new),
+            .unwrap_or_default(),
+        exclusions,
+        include_patterns: matches
+            .values_of("include")
+            .map(|v| v.map(String::from).collect())
+            .unwrap_or_default(),
         ignore_files: matches
             .values_of("ignore-file")
             .map(|vs| vs.map(PathBuf::from).collect())
-            .unwrap_or_else(Vec::new),
+            .unwrap_or_default(),
         size_constraints: size_limits,
         time_constraints,
+        disk_usage: matches.is_present("disk-usage"),
+        sparse_only: matches.is_present("sparse"),
+        dir_filter,
         #[cfg(unix)]
         owner_constraint,
+        #[cfg(unix)]
+        xattr_filter,
         show_filesystem_errors: matches.is_present("show-errors"),
         path_separator,
+        format: matches
+            .value_of("format")
+            .map(FormatTemplate::new)
+            .transpose()?,
         max_results: matches
             .value_of("max-results")
             .map(|n| n.parse::<usize>())
@@ -413,6 +740,16 @@ fn extract_command(
     .transpose()
 }
 
+fn extract_rename_template(
+    matches: &clap::ArgMatches,
+    pattern: &regex::bytes::Regex,
+) -> Result<Option<RenameTemplate>> {
+    matches
+        .value_of("rename")
+        .map(|template| RenameTemplate::new(template, pattern))
+        .transpose()
+}
+
 fn determine_ls_command(color_arg: &str, colored_output: bool) -> Result<Vec<&str>> {
     #[allow(unused)]
     let gnu_ls = |command_name| {
@@ -493,13 +830,45 @@ fn determine_ls_command(color_arg: &str, colored_output: bool) -> Result<Vec<&st
 }
 
 fn extract_size_limits(matches: &clap::ArgMatches) -> Result<Vec<SizeFilter>> {
-    matches.values_of("size").map_or(Ok(Vec::new()), |vs| {
-        vs.map(|sf| {
-            SizeFilter::from_string(sf)
-                .ok_or_else(|| anyhow!("'{}' is not a valid size constraint. See 'fd --help'.", sf))
+    let mut size_limits = matches
+        .values_of("size")
+        .into_iter()
+        .flatten()
+        .map(SizeFilter::parse)
+        .collect::<Result<Vec<_>>>()?;
+
+    // The size of a reference file, measured the same way as the size of the search results.
+    let reference_size = |path: &OsStr| -> Result<u64> {
+        let metadata = fs::metadata(path).with_context(|| {
+            format!(
+                "Could not determine the size of '{}'",
+                Path::new(path).display()
+            )
+        })?;
+        Ok(if matches.is_present("disk-usage") {
+            filesystem::allocated_size(&metadata)
+        } else {
+            metadata.len()
         })
-        .collect::<Result<Vec<_>>>()
-    })
+    };
+
+    if let Some(path) = matches.value_of_os("larger-than") {
+        let size = reference_size(path)?;
+        size_limits.push(SizeFilter::Min(size.saturating_add(1)));
+    }
+    if let Some(path) = matches.value_of_os("smaller-than") {
+        let size = reference_size(path)?;
+        if size == 0 {
+            return Err(anyhow!(
+                "No file can be smaller than '{}', it has a size of {}.",
+                Path::new(path).display(),
+                format_size(size)
+            ));
+        }
+        size_limits.push(SizeFilter::Max(size - 1));
+    }
+
+    Ok(size_limits)
 }
 
 fn extract_time_constraints(matches: &clap::ArgMatches) -> Result<Vec<TimeFilter>> {
@@ -544,6 +913,22 @@ fn ensure_use_hidden_option_for_leading_dot_pattern(
     }
 }
 
+/// Build a regex for an option like `--parent-pattern`. Like the search pattern, it is
+/// case-insensitive unless it contains an uppercase character (or a case option is given).
+fn build_option_regex(
+    matches: &clap::ArgMatches,
+    option: &str,
+    pattern: &str,
+) -> Result<regex::bytes::Regex> {
+    let case_sensitive = !matches.is_present("ignore-case")
+        && (matches.is_present("case-sensitive") || pattern_has_uppercase_char(pattern));
+    RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .dot_matches_new_line(true)
+        .build()
+        .map_err(|e| anyhow!("Invalid '{}': {}", option, e))
+}
+
 fn build_regex(pattern_regex: String, config: &Config) -> Result<regex::bytes::Regex> {
     RegexBuilder::new(&pattern_regex)
         .case_insensitive(!config.case_sensitive)
@@ -0,0 +1,210 @@
+use std::ffi::{OsStr, OsString};
+
+use anyhow::{anyhow, Result};
+use caseless::Caseless;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// A Unicode normalization form (`--normalize`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Form {
+    Nfc,
+    Nfd,
+    Nfkc,
+}
+
+/// Transforms file names and search patterns before they are compared, so that names which only
+/// differ in their Unicode representation, their case or their diacritics match the same
+/// patterns.
+#[derive(Clone, Debug)]
+pub struct NameNormalizer {
+    form: Option<Form>,
+    case_fold: bool,
+    ignore_diacritics: bool,
+}
+
+impl NameNormalizer {
+    /// Returns `None` if no transformation has been requested.
+    pub fn new(
+        form: Option<&str>,
+        case_fold: bool,
+        ignore_diacritics: bool,
+    ) -> Result<Option<NameNormalizer>> {
+        let form = match form {
+            None => None,
+            Some("nfc") => Some(Form::Nfc),
+            Some("nfd") => Some(Form::Nfd),
+            Some("nfkc") => Some(Form::Nfkc),
+            Some(other) => {
+                return Err(anyhow!(
+                    "'{}' is not a valid normalization form. Use 'nfc', 'nfd' or 'nfkc'.",
+                    other
+                ))
+            }
+        };
+
+        if form.is_none() && !case_fold && !ignore_diacritics {
+            return Ok(None);
+        }
+        Ok(Some(NameNormalizer {
+            form,
+            case_fold,
+            ignore_diacritics,
+        }))
+    }
+
+    pub fn normalize(&self, name: &str) -> String {
+        let mut name = if self.ignore_diacritics {
+            // Decompose the characters to separate the diacritics from their base characters.
+            let decomposed: Box<dyn Iterator<Item = char>> = if self.form == Some(Form::Nfkc) {
+                Box::new(name.nfkd())
+            } else {
+                Box::new(name.nfd())
+            };
+            decomposed.filter(|&c| !is_combining_mark(c)).collect()
+        } else {
+            name.to_owned()
+        };
+
+        if self.case_fold {
+            name = name.chars().default_case_fold().collect();
+        }
+
+        match self.form {
+            Some(Form::Nfc) => name.nfc().collect(),
+            Some(Form::Nfd) => name.nfd().collect(),
+            Some(Form::Nfkc) => name.nfkc().collect(),
+            None => name,
+        }
+    }
+
+    pub fn normalize_os(&self, name: &OsStr) -> OsString {
+        OsString::from(self.normalize(&name.to_string_lossy()))
+    }
+
+    /// Normalize the literal characters of a regular expression. Only non-ASCII characters are
+    /// changed (together with an ASCII letter that is followed by a combining mark), so that the
+    /// syntax of the regex stays intact. ASCII letters are matched case-insensitively instead of
+    /// being folded.
+    pub fn normalize_regex(&self, pattern: &str) -> String {
+        self.normalize_literals(pattern, regex::escape)
+    }
+
+    /// Normalize the literal characters of a glob, in the same way as `normalize_regex`. This has
+    /// to happen before the glob is translated, because the regex of a glob only contains the
+    /// escaped bytes of non-ASCII characters.
+    pub fn normalize_glob(&self, pattern: &str) -> String {
+        self.normalize_literals(pattern, escape_glob)
+    }
+
+    fn normalize_literals(&self, pattern: &str, escape: fn(&str) -> String) -> String {
+        let mut result = String::with_capacity(pattern.len());
+        let mut run = String::new();
+        let mut escaped = false;
+        let mut after_literal_letter = false;
+
+        for c in pattern.chars() {
+            if !c.is_ascii() {
+                if run.is_empty() && after_literal_letter && is_combining_mark(c) {
+                    run.extend(result.pop());
+                }
+                run.push(c);
+                after_literal_letter = false;
+                continue;
+            }
+
+            if !run.is_empty() {
+                result.push_str(&escape(&self.normalize(&run)));
+                run.clear();
+            }
+            after_literal_letter = !escaped && c.is_ascii_alphabetic();
+            escaped = !escaped && c == '\\';
+            result.push(c);
+        }
+        if !run.is_empty() {
+            result.push_str(&escape(&self.normalize(&run)));
+        }
+
+        result
+    }
+}
+
+/// Escape the characters that have a special meaning in a glob, by wrapping each of them in a
+/// character class (a backslash is not an escape character on every platform).
+fn escape_glob(literal: &str) -> String {
+    let mut result = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if "*?[]{},\\".contains(c) {
+            result.push('[');
+            result.push(c);
+            result.push(']');
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NameNormalizer;
+
+    fn normalizer(form: Option<&str>, case_fold: bool, ignore_diacritics: bool) -> NameNormalizer {
+        NameNormalizer::new(form, case_fold, ignore_diacritics)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn normalization_forms() {
+        let nfc = normalizer(Some("nfc"), false, false);
+        assert_eq!(nfc.normalize("Cafe\u{301}"), "Caf\u{e9}");
+        let nfd = normalizer(Some("nfd"), false, false);
+        assert_eq!(nfd.normalize("Caf\u{e9}"), "Cafe\u{301}");
+        let nfkc = normalizer(Some("nfkc"), false, false);
+        assert_eq!(nfkc.normalize("\u{fb01}le"), "file");
+
+        assert!(NameNormalizer::new(Some("nfx"), false, false).is_err());
+        assert!(NameNormalizer::new(None, false, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn case_folding_and_diacritics() {
+        let fold = normalizer(None, true, false);
+        assert_eq!(fold.normalize("Straße"), "strasse");
+
+        let diacritics = normalizer(Some("nfc"), false, true);
+        assert_eq!(diacritics.normalize("Crème brûlée"), "Creme brulee");
+        assert_eq!(diacritics.normalize("Cre\u{300}me"), "Creme");
+    }
+
+    #[test]
+    fn regex_syntax_is_preserved() {
+        let both = normalizer(Some("nfc"), true, true);
+        assert_eq!(
+            both.normalize_regex(r"^\WStraße\.txt$"),
+            r"^\WStrasse\.txt$"
+        );
+        assert_eq!(both.normalize_regex("Cafe\u{301}"), "Cafe");
+
+        let nfc = normalizer(Some("nfc"), false, false);
+        assert_eq!(nfc.normalize_regex("Cafe\u{301}"), "Caf\u{e9}");
+
+        let nfkc = normalizer(Some("nfkc"), false, false);
+        assert_eq!(nfkc.normalize_regex("a\u{ff0a}"), r"a\*");
+    }
+
+    #[test]
+    fn glob_syntax_is_preserved() {
+        let both = normalizer(Some("nfc"), true, true);
+        assert_eq!(both.normalize_glob("Straße*.{txt,md}"), "Strasse*.{txt,md}");
+        assert_eq!(both.normalize_glob("Cafe\u{301}?"), "Cafe?");
+
+        let nfkc = normalizer(Some("nfkc"), false, false);
+        assert_eq!(nfkc.normalize_glob("a\u{ff0a}*"), "a[*]*");
+        assert_eq!(
+            nfkc.normalize_glob("\u{ff5b}a\u{ff0c}b\u{ff5d}"),
+            "[{]a[,]b[}]"
+        );
+    }
+}
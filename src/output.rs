@@ -1,5 +1,5 @@
 use std::io::{self, StdoutLock, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
@@ -9,7 +9,8 @@ use lscolors::{LsColors, Style};
 use crate::config::Config;
 use crate::error::print_error;
 use crate::exit_codes::ExitCode;
-use crate::filesystem::strip_current_dir;
+use crate::filesystem::{self, strip_current_dir};
+use crate::format::FormatTemplate;
 
 fn replace_path_separator(path: &str, new_path_separator: &str) -> String {
     path.replace(std::path::MAIN_SEPARATOR, new_path_separator)
@@ -20,6 +21,7 @@ pub fn print_entry(
     stdout: &mut StdoutLock,
     entry: &Path,
     config: &Config,
+    search_paths: &[PathBuf],
     wants_to_quit: &Arc<AtomicBool>,
 ) {
     let path = if entry.is_absolute() {
@@ -28,7 +30,15 @@ pub fn print_entry(
         strip_current_dir(entry)
     };
 
-    let r = if let Some(ref ls_colors) = config.ls_colors {
+    let r = if let Some(ref format) = config.format {
+        let root = filesystem::search_root_of(entry, search_paths);
+        let pattern = if format.uses_pattern() {
+            matched_pattern(entry, root, config)
+        } else {
+            None
+        };
+        print_entry_formatted(stdout, path, root, pattern, format, config)
+    } else if let Some(ref ls_colors) = config.ls_colors {
         print_entry_colorized(stdout, path, config, ls_colors, wants_to_quit)
     } else {
         print_entry_uncolorized(stdout, path, config)
@@ -45,6 +55,48 @@ pub fn print_entry(
     }
 }
 
+/// The first search pattern that matches the entry, for the `{pattern}` placeholder.
+fn matched_pattern<'a>(entry: &Path, root: Option<&Path>, config: &'a Config) -> Option<&'a str> {
+    let (set, patterns) = config.search_patterns.as_ref()?;
+    let search_str = if config.search_full_path {
+        filesystem::path_absolute_form(entry).ok()?.into_os_string()
+    } else if config.search_relative_path {
+        let depth = entry.strip_prefix(root?).ok()?.components().count();
+        filesystem::root_relative_path(entry, depth)
+    } else {
+        entry.file_name()?.to_os_string()
+    };
+    let search_str = match config.normalizer {
+        Some(ref normalizer) => normalizer.normalize_os(&search_str),
+        None => search_str,
+    };
+    let index = set
+        .matches(&filesystem::osstr_to_bytes(&search_str))
+        .into_iter()
+        .next()?;
+    Some(&patterns[index])
+}
+
+fn print_entry_formatted(
+    stdout: &mut StdoutLock,
+    path: &Path,
+    root: Option<&Path>,
+    pattern: Option<&str>,
+    format: &FormatTemplate,
+    config: &Config,
+) -> io::Result<()> {
+    let separator = if config.null_separator { "\0" } else { "\n" };
+
+    let mut line = format
+        .generate(path, root, pattern)
+        .to_string_lossy()
+        .into_owned();
+    if let Some(ref separator) = config.path_separator {
+        line = replace_path_separator(&line, separator);
+    }
+    write!(stdout, "{}{}", line, separator)
+}
+
 // TODO: this function is performance critical and can probably be optimized
 fn print_entry_colorized(
     stdout: &mut StdoutLock,
@@ -1,8 +1,9 @@
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ffi::OsStr;
 use std::fs::{FileType, Metadata};
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::process;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{channel, Receiver, Sender};
@@ -11,16 +12,20 @@ use std::thread;
 use std::time;
 
 use anyhow::{anyhow, Result};
-use ignore::overrides::OverrideBuilder;
+use ignore::overrides::{Override, OverrideBuilder};
 use ignore::{self, WalkBuilder};
 use once_cell::unsync::OnceCell;
 use regex::bytes::Regex;
 
+use crate::action;
+use crate::archive::{self, ArchiveFormat};
 use crate::config::Config;
 use crate::error::print_error;
 use crate::exec;
 use crate::exit_codes::{merge_exitcodes, ExitCode};
 use crate::filesystem;
+use crate::filter::Content;
+use crate::fuzzy::FuzzyMatcher;
 use crate::output;
 
 /// The receiver thread can either be buffering results or directly streaming to the console.
@@ -33,6 +38,23 @@ enum ReceiverMode {
     Streaming,
 }
 
+/// The order in which a single-threaded traversal visits the entries (`--traversal`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Traversal {
+    /// Every directory is followed by its contents.
+    DepthFirst,
+    /// All entries of one level come before the entries of the next level.
+    BreadthFirst,
+}
+
+/// Where directories are placed among the other entries of their parent directory
+/// (`--dirs-first`, `--dirs-last`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DirectoryOrder {
+    First,
+    Last,
+}
+
 /// The Worker threads can result in a valid entry having PathBuf or an error.
 pub enum WorkerResult {
     Entry(PathBuf),
@@ -44,30 +66,244 @@ pub const MAX_BUFFER_LENGTH: usize = 1000;
 /// Default duration until output buffering switches to streaming.
 pub const DEFAULT_MAX_BUFFER_TIME: time::Duration = time::Duration::from_millis(100);
 
+/// The path of the global fd ignore file (`fd/ignore` in the config directory), if it exists.
+pub fn global_ignore_file() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    let config_dir_op = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| dirs_next::home_dir().map(|d| d.join(".config")));
+
+    #[cfg(not(target_os = "macos"))]
+    let config_dir_op = dirs_next::config_dir();
+
+    config_dir_op
+        .map(|p| p.join("fd").join("ignore"))
+        .filter(|p| p.is_file())
+}
+
 /// Recursively scan the given search path for files / pathnames matching the pattern.
 ///
 /// If the `--exec` argument was supplied, this will create a thread pool for executing
 /// jobs in parallel from a given command line and the discovered paths. Otherwise, each
 /// path will simply be written to standard output.
 pub fn scan(path_vec: &[PathBuf], pattern: Arc<Regex>, config: Arc<Config>) -> Result<ExitCode> {
-    let mut path_iter = path_vec.iter();
-    let first_path_buf = path_iter
-        .next()
-        .expect("Error: Path vector can not be empty");
+    assert!(!path_vec.is_empty(), "Error: Path vector can not be empty");
+    let (tx, rx) = channel();
+
+    // Every search path is traversed on its own, so that the '--exclude' and '--include'
+    // patterns are relative to the search path that an entry was found in.
+    let mut walkers = Vec::with_capacity(path_vec.len());
+    let mut include_walkers = Vec::new();
+    for (index, path) in path_vec.iter().enumerate() {
+        let overrides = build_overrides(path, &config.exclude_patterns, "exclude")?;
+
+        // Entries that match an '--include' pattern are found by a second traversal, which does
+        // not respect hidden and ignored files.
+        if !config.include_patterns.is_empty() {
+            let includes = build_overrides(path, &config.include_patterns, "include")?;
+            let mut walker = WalkBuilder::new(path);
+            walker
+                .standard_filters(false)
+                .overrides(overrides.clone())
+                .follow_links(config.follow_links)
+                .same_file_system(config.one_file_system)
+                .max_depth(config.max_depth);
+            include_walkers.push((walker, overrides.clone(), includes));
+        }
+
+        // Problems with the ignore files only need to be reported once.
+        let walker = build_walker(path, overrides.clone(), &config, index == 0);
+        walkers.push((walker, overrides));
+    }
+
+    let wants_to_quit = Arc::new(AtomicBool::new(false));
+    if config.ls_colors.is_some() && config.command.is_none() {
+        let wq = Arc::clone(&wants_to_quit);
+        ctrlc::set_handler(move || {
+            if wq.load(Ordering::Relaxed) {
+                // Ctrl-C has been pressed twice, exit NOW
+                process::exit(ExitCode::KilledBySigint.into());
+            } else {
+                wq.store(true, Ordering::Relaxed);
+            }
+        })
+        .unwrap();
+    }
+
+    // Entries can be found by both traversals if '--include' is used.
+    let rx = if include_walkers.is_empty() {
+        rx
+    } else {
+        deduplicate(rx)
+    };
+    let rx = if config.traversal == Some(Traversal::DepthFirst)
+        && config.directory_order == Some(DirectoryOrder::Last)
+    {
+        contents_first(rx, config.follow_links)
+    } else {
+        rx
+    };
+
+    // Spawn the thread that receives all results through the channel.
+    let receiver_thread = spawn_receiver(&config, &wants_to_quit, &pattern, path_vec, rx);
+
+    // Directories can be reached through several search paths, or through symbolic links. These
+    // are only traversed once.
+    let needs_deduplication = path_vec.len() > 1 || config.follow_links;
+    let visited = VisitedEntries::new(path_vec);
+    let visited_includes = VisitedEntries::new(path_vec);
+
+    // Spawn the sender threads.
+    for (index, (walker, overrides)) in walkers.into_iter().enumerate() {
+        let context = SenderContext {
+            config: &config,
+            wants_to_quit: &wants_to_quit,
+            pattern: &pattern,
+            overrides: &overrides,
+            includes: None,
+            visited: if needs_deduplication {
+                Some((&visited, index))
+            } else {
+                None
+            },
+        };
+        spawn_senders(&context, walker, tx.clone());
+    }
+    for (index, (include_walker, overrides, includes)) in include_walkers.into_iter().enumerate() {
+        let context = SenderContext {
+            config: &config,
+            wants_to_quit: &wants_to_quit,
+            pattern: &pattern,
+            overrides: &overrides,
+            includes: Some(&includes),
+            visited: if needs_deduplication {
+                Some((&visited_includes, index))
+            } else {
+                None
+            },
+        };
+        spawn_senders(&context, include_walker, tx.clone());
+    }
+    drop(tx);
+
+    // Wait for the receiver thread to print out all results.
+    let exit_code = receiver_thread.join().unwrap();
+
+    if wants_to_quit.load(Ordering::Relaxed) {
+        Ok(ExitCode::KilledBySigint)
+    } else {
+        Ok(exit_code)
+    }
+}
+
+/// Apply the search pattern and all filters to the given paths, instead of traversing the file
+/// system (`--paths-from`).
+pub fn filter_paths(
+    paths: Vec<PathBuf>,
+    pattern: Arc<Regex>,
+    config: Arc<Config>,
+) -> Result<ExitCode> {
     let (tx, rx) = channel();
+    let wants_to_quit = Arc::new(AtomicBool::new(false));
+
+    // Every path gets a search root, which the actions and '{root}' refer to.
+    let paths: Vec<(PathBuf, PathBuf)> = paths.into_iter().map(listed_path_with_root).collect();
+    let mut search_paths: Vec<PathBuf> = Vec::new();
+    for (_, root) in &paths {
+        if !search_paths.contains(root) {
+            search_paths.push(root.clone());
+        }
+    }
+    let receiver_thread = spawn_receiver(&config, &wants_to_quit, &pattern, &search_paths, rx);
+
+    for (path, _) in paths {
+        if let Err(err) = path.symlink_metadata() {
+            let error = ignore::Error::WithPath {
+                path: filesystem::strip_current_dir(&path).to_path_buf(),
+                err: Box::new(ignore::Error::Io(err)),
+            };
+            if tx.send(WorkerResult::Error(error)).is_err() {
+                break;
+            }
+            continue;
+        }
+
+        let depth = path
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .count();
+        let entry = DirEntry::from_path(path, depth, config.follow_links);
+        if config.exclusions.iter().any(|e| e.matches(&entry)) {
+            continue;
+        }
+
+        if config.search_archives && entry.file_type().map_or(false, |ft| ft.is_file()) {
+            if let Some(format) = ArchiveFormat::from_path(entry.path()) {
+                if !search_archive(&config, &pattern, &entry, format, None, &tx) {
+                    break;
+                }
+            }
+        }
+
+        if filter_entry(&config, &pattern, &entry).is_some() {
+            continue;
+        }
+
+        if tx
+            .send(WorkerResult::Entry(entry.path().to_owned()))
+            .is_err()
+        {
+            break;
+        }
+    }
+    drop(tx);
+
+    Ok(receiver_thread.join().unwrap())
+}
 
-    let mut override_builder = OverrideBuilder::new(first_path_buf.as_path());
+/// A path from '--paths-from' together with its search root. Relative paths are found in the
+/// current directory, like in a search of '.'. Absolute paths, and relative paths that leave the
+/// current directory, are found in their parent directory.
+fn listed_path_with_root(path: PathBuf) -> (PathBuf, PathBuf) {
+    let leaves_current_dir = path.is_absolute()
+        || path
+            .components()
+            .any(|c| matches!(c, Component::ParentDir | Component::Prefix(_)));
+    if leaves_current_dir {
+        let root = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ if path.has_root() => PathBuf::from("/"),
+            _ => PathBuf::from("."),
+        };
+        (path, root)
+    } else if path.starts_with(".") {
+        (path, PathBuf::from("."))
+    } else {
+        (Path::new(".").join(path), PathBuf::from("."))
+    }
+}
 
-    for pattern in &config.exclude_patterns {
+fn build_overrides(root: &Path, patterns: &[String], kind: &str) -> Result<Override> {
+    let mut override_builder = OverrideBuilder::new(root);
+    for pattern in patterns {
         override_builder
             .add(pattern)
-            .map_err(|e| anyhow!("Malformed exclude pattern: {}", e))?;
+            .map_err(|e| anyhow!("Malformed {} pattern: {}", kind, e))?;
     }
-    let overrides = override_builder
+    override_builder
         .build()
-        .map_err(|_| anyhow!("Mismatch in exclude patterns"))?;
+        .map_err(|_| anyhow!("Mismatch in {} patterns", kind))
+}
 
-    let mut walker = WalkBuilder::new(first_path_buf.as_path());
+/// Set up the traversal of a single search path.
+fn build_walker(
+    root: &Path,
+    overrides: Override,
+    config: &Config,
+    report_errors: bool,
+) -> WalkBuilder {
+    let mut walker = WalkBuilder::new(root);
     walker
         .hidden(config.ignore_hidden)
         .ignore(config.read_fdignore)
@@ -86,29 +322,14 @@ pub fn scan(path_vec: &[PathBuf], pattern: Arc<Regex>, config: Arc<Config>) -> R
     }
 
     if config.read_global_ignore {
-        #[cfg(target_os = "macos")]
-        let config_dir_op = std::env::var_os("XDG_CONFIG_HOME")
-            .map(PathBuf::from)
-            .filter(|p| p.is_absolute())
-            .or_else(|| dirs_next::home_dir().map(|d| d.join(".config")));
-
-        #[cfg(not(target_os = "macos"))]
-        let config_dir_op = dirs_next::config_dir();
-
-        if let Some(global_ignore_file) = config_dir_op
-            .map(|p| p.join("fd").join("ignore"))
-            .filter(|p| p.is_file())
-        {
+        if let Some(global_ignore_file) = global_ignore_file() {
             let result = walker.add_ignore(global_ignore_file);
             match result {
                 Some(ignore::Error::Partial(_)) => (),
-                Some(err) => {
-                    print_error(format!(
-                        "Malformed pattern in global ignore file. {}.",
-                        err.to_string()
-                    ));
+                Some(err) if report_errors => {
+                    print_error(format!("Malformed pattern in global ignore file. {}.", err));
                 }
-                None => (),
+                _ => (),
             }
         }
     }
@@ -117,59 +338,151 @@ pub fn scan(path_vec: &[PathBuf], pattern: Arc<Regex>, config: Arc<Config>) -> R
         let result = walker.add_ignore(ignore_file);
         match result {
             Some(ignore::Error::Partial(_)) => (),
-            Some(err) => {
-                print_error(format!(
-                    "Malformed pattern in custom ignore file. {}.",
-                    err.to_string()
-                ));
+            Some(err) if report_errors => {
+                print_error(format!("Malformed pattern in custom ignore file. {}.", err));
             }
-            None => (),
+            _ => (),
         }
     }
 
-    for path_entry in path_iter {
-        walker.add(path_entry.as_path());
-    }
+    walker
+}
 
-    let parallel_walker = walker.threads(config.threads).build_parallel();
+#[cfg(unix)]
+type FileId = (u64, u64);
 
-    let wants_to_quit = Arc::new(AtomicBool::new(false));
-    if config.ls_colors.is_some() && config.command.is_none() {
-        let wq = Arc::clone(&wants_to_quit);
-        ctrlc::set_handler(move || {
-            if wq.load(Ordering::Relaxed) {
-                // Ctrl-C has been pressed twice, exit NOW
-                process::exit(ExitCode::KilledBySigint.into());
-            } else {
-                wq.store(true, Ordering::Relaxed);
-            }
-        })
-        .unwrap();
-    }
+#[cfg(not(unix))]
+type FileId = PathBuf;
 
-    // Spawn the thread that receives all results through the channel.
-    let receiver_thread = spawn_receiver(&config, &wants_to_quit, rx);
+#[cfg(unix)]
+fn file_id(entry: &DirEntry) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
 
-    // Spawn the sender threads.
-    spawn_senders(&config, &wants_to_quit, pattern, parallel_walker, tx);
+    entry.metadata().map(|m| (m.dev(), m.ino()))
+}
 
-    // Wait for the receiver thread to print out all results.
-    let exit_code = receiver_thread.join().unwrap();
+#[cfg(not(unix))]
+fn file_id(entry: &DirEntry) -> Option<FileId> {
+    entry.path().canonicalize().ok()
+}
 
-    if wants_to_quit.load(Ordering::Relaxed) {
-        Ok(ExitCode::KilledBySigint)
-    } else {
-        Ok(exit_code)
+/// Whether an entry has been visited by one of the traversals before.
+enum Visit {
+    New,
+    /// The entry is a search path that has already been traversed, but it has not been reported.
+    SearchPath,
+    /// The entry is a symbolic link to a directory inside of a search path, whose contents are
+    /// found through the real path.
+    Link,
+    Seen,
+}
+
+/// The directories that have been visited by the traversals so far, identified by their device
+/// and inode number, together with the index of the search path they were found in.
+///
+/// Only directories are tracked: distinct paths to the same file (hard links) are all reported.
+/// With `--follow`, symbolic links to directories inside of a search path are reported, but not
+/// traversed, so that their contents are only found through the real path. Other directories
+/// that are reached through several symbolic links are traversed once.
+struct VisitedEntries {
+    directories: Mutex<HashMap<FileId, usize>>,
+    search_paths: Mutex<HashSet<FileId>>,
+    /// The targets of the symbolic links to directories outside of the search paths.
+    links: Mutex<HashSet<PathBuf>>,
+    /// The canonical form of all search paths.
+    roots: Vec<PathBuf>,
+}
+
+impl VisitedEntries {
+    fn new(search_paths: &[PathBuf]) -> VisitedEntries {
+        VisitedEntries {
+            directories: Mutex::default(),
+            search_paths: Mutex::default(),
+            links: Mutex::default(),
+            roots: search_paths
+                .iter()
+                .filter_map(|path| path.canonicalize().ok())
+                .collect(),
+        }
+    }
+
+    /// Remember the root of a traversal. Returns `false` if it has been visited before.
+    fn insert_search_path(&self, entry: &DirEntry) -> bool {
+        match file_id(entry) {
+            Some(id) => {
+                !self.directories.lock().unwrap().contains_key(&id)
+                    && self.search_paths.lock().unwrap().insert(id)
+            }
+            None => true,
+        }
+    }
+
+    /// Remember an entry that was found by the traversal of the search path with index `root`.
+    fn insert(&self, entry: &DirEntry, root: usize) -> Visit {
+        if !entry.file_type().map_or(false, |ft| ft.is_dir()) {
+            return Visit::New;
+        }
+        let id = match file_id(entry) {
+            Some(id) => id,
+            None => return Visit::New,
+        };
+
+        let is_link = entry
+            .path()
+            .symlink_metadata()
+            .map_or(false, |m| m.file_type().is_symlink());
+        if is_link {
+            if let Ok(target) = entry.path().canonicalize() {
+                if self.roots.iter().any(|root| target.starts_with(root)) {
+                    return Visit::Link;
+                }
+                if !self.links.lock().unwrap().insert(target) {
+                    return Visit::Seen;
+                }
+            }
+        }
+
+        let is_search_path = self.search_paths.lock().unwrap().contains(&id);
+        if *self.directories.lock().unwrap().entry(id).or_insert(root) != root {
+            Visit::Seen
+        } else if is_search_path {
+            Visit::SearchPath
+        } else {
+            Visit::New
+        }
     }
 }
 
+/// Forward all results, but drop entries that have already been sent before.
+fn deduplicate(rx: Receiver<WorkerResult>) -> Receiver<WorkerResult> {
+    let (tx, deduplicated_rx) = channel();
+    thread::spawn(move || {
+        let mut seen = HashSet::new();
+        for result in rx {
+            if let WorkerResult::Entry(ref path) = result {
+                if !seen.insert(path.clone()) {
+                    continue;
+                }
+            }
+            if tx.send(result).is_err() {
+                break;
+            }
+        }
+    });
+    deduplicated_rx
+}
+
 fn spawn_receiver(
     config: &Arc<Config>,
     wants_to_quit: &Arc<AtomicBool>,
+    pattern: &Arc<Regex>,
+    search_paths: &[PathBuf],
     rx: Receiver<WorkerResult>,
 ) -> thread::JoinHandle<ExitCode> {
     let config = Arc::clone(config);
     let wants_to_quit = Arc::clone(wants_to_quit);
+    let pattern = Arc::clone(pattern);
+    let search_paths = search_paths.to_vec();
 
     let show_filesystem_errors = config.show_filesystem_errors;
     let threads = config.threads;
@@ -214,6 +527,16 @@ fn spawn_receiver(
                     .collect::<Vec<_>>();
                 merge_exitcodes(exit_codes)
             }
+        } else if let Some(ref template) = config.rename {
+            action::rename(rx, template, &pattern, &config)
+        } else if config.delete {
+            action::delete(rx, &search_paths, &config)
+        } else if let Some((mode, ref destination)) = config.transfer {
+            action::transfer(rx, mode, destination, &search_paths, &config)
+        } else if let Some((format, ref output)) = config.archive {
+            action::archive(rx, format, output, &search_paths, &config)
+        } else if let Some(ref fuzzy) = config.fuzzy {
+            print_ranked(rx, fuzzy, &config, &search_paths, &wants_to_quit)
         } else {
             let start = time::Instant::now();
 
@@ -251,6 +574,7 @@ fn spawn_receiver(
                                             &mut stdout,
                                             v,
                                             &config,
+                                            &search_paths,
                                             &wants_to_quit,
                                         );
                                     }
@@ -261,7 +585,13 @@ fn spawn_receiver(
                                 }
                             }
                             ReceiverMode::Streaming => {
-                                output::print_entry(&mut stdout, &value, &config, &wants_to_quit);
+                                output::print_entry(
+                                    &mut stdout,
+                                    &value,
+                                    &config,
+                                    &search_paths,
+                                    &wants_to_quit,
+                                );
                             }
                         }
 
@@ -281,10 +611,13 @@ fn spawn_receiver(
             }
 
             // If we have finished fast enough (faster than max_buffer_time), we haven't streamed
-            // anything to the console, yet. In this case, sort the results and print them:
-            buffer.sort();
+            // anything to the console, yet. In this case, sort the results and print them, unless
+            // they already arrive in the order of the traversal:
+            if config.traversal.is_none() {
+                buffer.sort();
+            }
             for value in buffer {
-                output::print_entry(&mut stdout, &value, &config, &wants_to_quit);
+                output::print_entry(&mut stdout, &value, &config, &search_paths, &wants_to_quit);
             }
 
             if config.quiet {
@@ -296,9 +629,68 @@ fn spawn_receiver(
     })
 }
 
+/// Print the results of a fuzzy search (`--fuzzy`), best matches first. Since the ranking depends
+/// on all results, nothing is printed before the search has finished.
+fn print_ranked(
+    rx: Receiver<WorkerResult>,
+    fuzzy: &FuzzyMatcher,
+    config: &Config,
+    search_paths: &[PathBuf],
+    wants_to_quit: &Arc<AtomicBool>,
+) -> ExitCode {
+    let mut results = Vec::new();
+    for worker_result in rx {
+        match worker_result {
+            WorkerResult::Entry(path) => {
+                if config.quiet {
+                    return ExitCode::HasResults(true);
+                }
+                let score = fuzzy
+                    .score_path(&path, config.search_full_path, config.normalizer.as_ref())
+                    .unwrap_or(0);
+                results.push((score, path));
+            }
+            WorkerResult::Error(err) => {
+                if config.show_filesystem_errors {
+                    print_error(err.to_string());
+                }
+            }
+        }
+    }
+
+    if config.quiet {
+        return ExitCode::HasResults(false);
+    }
+
+    // Prefer higher scores, then shorter paths.
+    results.sort_by(|(score_a, path_a), (score_b, path_b)| {
+        score_b
+            .cmp(score_a)
+            .then_with(|| path_a.as_os_str().len().cmp(&path_b.as_os_str().len()))
+            .then_with(|| path_a.cmp(path_b))
+    });
+    if let Some(max_results) = config.max_results {
+        results.truncate(max_results);
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for (_, path) in results {
+        output::print_entry(&mut stdout, &path, config, search_paths, wants_to_quit);
+    }
+
+    ExitCode::Success
+}
+
 enum DirEntryInner {
-    Normal(ignore::DirEntry),
+    /// An entry of a traversal that started the given number of levels below the search path.
+    Normal(ignore::DirEntry, usize),
     BrokenSymlink(PathBuf),
+    Path {
+        path: PathBuf,
+        depth: usize,
+        follow_links: bool,
+    },
 }
 
 pub struct DirEntry {
@@ -307,9 +699,9 @@ pub struct DirEntry {
 }
 
 impl DirEntry {
-    fn normal(e: ignore::DirEntry) -> Self {
+    fn normal(e: ignore::DirEntry, depth_offset: usize) -> Self {
         Self {
-            inner: DirEntryInner::Normal(e),
+            inner: DirEntryInner::Normal(e, depth_offset),
             metadata: OnceCell::new(),
         }
     }
@@ -321,199 +713,873 @@ impl DirEntry {
         }
     }
 
+    /// An entry that was not found by the directory traversal, e.g. the path given to
+    /// `--explain`. Symbolic links are followed like in the traversal.
+    pub fn from_path(path: PathBuf, depth: usize, follow_links: bool) -> Self {
+        Self {
+            inner: DirEntryInner::Path {
+                path,
+                depth,
+                follow_links,
+            },
+            metadata: OnceCell::new(),
+        }
+    }
+
     pub fn path(&self) -> &Path {
         match &self.inner {
-            DirEntryInner::Normal(e) => e.path(),
+            DirEntryInner::Normal(e, _) => e.path(),
             DirEntryInner::BrokenSymlink(pathbuf) => pathbuf.as_path(),
+            DirEntryInner::Path { path, .. } => path.as_path(),
         }
     }
 
     pub fn file_type(&self) -> Option<FileType> {
         match &self.inner {
-            DirEntryInner::Normal(e) => e.file_type(),
-            DirEntryInner::BrokenSymlink(_) => self.metadata().map(|m| m.file_type()),
+            DirEntryInner::Normal(e, _) => e.file_type(),
+            DirEntryInner::BrokenSymlink(_) | DirEntryInner::Path { .. } => {
+                self.metadata().map(|m| m.file_type())
+            }
         }
     }
 
     pub fn metadata(&self) -> Option<&Metadata> {
         self.metadata
             .get_or_init(|| match &self.inner {
-                DirEntryInner::Normal(e) => e.metadata().ok(),
+                DirEntryInner::Normal(e, _) => e.metadata().ok(),
                 DirEntryInner::BrokenSymlink(path) => path.symlink_metadata().ok(),
+                DirEntryInner::Path {
+                    path, follow_links, ..
+                } => if *follow_links {
+                    path.metadata()
+                } else {
+                    path.symlink_metadata()
+                }
+                .or_else(|_| path.symlink_metadata())
+                .ok(),
             })
             .as_ref()
     }
 
     pub fn depth(&self) -> Option<usize> {
         match &self.inner {
-            DirEntryInner::Normal(e) => Some(e.depth()),
+            DirEntryInner::Normal(e, depth_offset) => Some(e.depth() + depth_offset),
             DirEntryInner::BrokenSymlink(_) => None,
+            DirEntryInner::Path { depth, .. } => Some(*depth),
         }
     }
 }
 
-fn spawn_senders(
-    config: &Arc<Config>,
-    wants_to_quit: &Arc<AtomicBool>,
-    pattern: Arc<Regex>,
-    parallel_walker: ignore::WalkParallel,
-    tx: Sender<WorkerResult>,
-) {
-    parallel_walker.run(|| {
-        let config = Arc::clone(config);
-        let pattern = Arc::clone(&pattern);
-        let tx_thread = tx.clone();
-        let wants_to_quit = Arc::clone(wants_to_quit);
+/// Match the entries of an archive against the search pattern and the filters that can be
+/// applied to archive members (`--search-archives`). Matching members are reported as
+/// `path/to/archive.zip!/inner/path`. Returns `false` if the receiver has quit.
+fn search_archive(
+    config: &Config,
+    pattern: &Regex,
+    archive: &DirEntry,
+    format: ArchiveFormat,
+    overrides: Option<&Override>,
+    tx: &Sender<WorkerResult>,
+) -> bool {
+    let archive_path = archive.path();
+    let members = match archive::members(archive_path, format) {
+        Ok(members) => members,
+        Err(err) => {
+            return tx
+                .send(WorkerResult::Error(ignore::Error::WithPath {
+                    path: archive_path.to_owned(),
+                    err: Box::new(ignore::Error::Io(err)),
+                }))
+                .is_ok();
+        }
+    };
+
+    let archive_search_path = if config.search_full_path {
+        filesystem::path_absolute_form(archive_path).expect("Retrieving absolute path succeeds")
+    } else if config.search_relative_path {
+        let depth = archive
+            .depth()
+            .unwrap_or_else(|| archive_path.components().count());
+        PathBuf::from(filesystem::root_relative_path(archive_path, depth))
+    } else {
+        archive_path.to_owned()
+    };
+
+    for member in members {
+        let depth = archive.depth().map(|d| d + member.path.split('/').count());
+        if config.min_depth.is_some() || config.max_depth.is_some() {
+            let within_depth = depth.map_or(false, |depth| {
+                config.min_depth.map_or(true, |min| depth >= min)
+                    && config.max_depth.map_or(true, |max| depth <= max)
+            });
+            if !within_depth {
+                continue;
+            }
+        }
+
+        // Members are hidden and excluded like the entries of a directory would be.
+        if config.ignore_hidden && member.path.split('/').any(|c| c.starts_with('.')) {
+            continue;
+        }
+        if let Some(overrides) = overrides {
+            if is_excluded_member(overrides, archive_path, &member) {
+                continue;
+            }
+        }
+
+        let mut member_path = archive_path.as_os_str().to_os_string();
+        member_path.push("!/");
+        member_path.push(&member.path);
+
+        if config
+            .exclusions
+            .iter()
+            .any(|e| e.matches_member(&member, Path::new(&member_path)))
+        {
+            continue;
+        }
+
+        let search_str = if config.search_full_path || config.search_relative_path {
+            let mut search_str = archive_search_path.as_os_str().to_os_string();
+            search_str.push("!/");
+            search_str.push(&member.path);
+            Cow::Owned(search_str)
+        } else {
+            Cow::Borrowed(OsStr::new(member.file_name()))
+        };
+        let search_str = match config.normalizer {
+            Some(ref normalizer) => Cow::Owned(normalizer.normalize_os(&search_str)),
+            None => search_str,
+        };
+        let matched = match config.fuzzy {
+            Some(ref fuzzy) => fuzzy.score(&search_str.to_string_lossy()).is_some(),
+            None => pattern.is_match(&filesystem::osstr_to_bytes(&search_str)),
+        };
+        if !matched {
+            continue;
+        }
+
+        if let Some(ref parent_pattern) = config.parent_pattern {
+            if !has_matching_ancestor(parent_pattern, Path::new(&member_path), depth) {
+                continue;
+            }
+        }
+
+        if let Some(ref exts_regex) = config.extensions {
+            if !exts_regex.is_match(member.file_name().as_bytes()) {
+                continue;
+            }
+        }
 
-        Box::new(move |entry_o| {
-            if wants_to_quit.load(Ordering::Relaxed) {
-                return ignore::WalkState::Quit;
+        if let Some(ref file_types) = config.file_types {
+            if file_types.should_ignore_archive_member(&member) {
+                continue;
             }
+        }
+
+        // The content of archive members is not inspected, they can only match a kind by name.
+        if config.content_filter.is_some() || config.text_files.is_some() {
+            continue;
+        }
+        // Symbolic links in archives are not resolved.
+        if config.symlink_filter.is_some() {
+            continue;
+        }
+        // Archive members do not carry extended attributes.
+        #[cfg(unix)]
+        {
+            if config.xattr_filter.is_some() {
+                continue;
+            }
+        }
+        if let Some(ref kind_filter) = config.kind_filter {
+            if !kind_filter.matches_name(OsStr::new(member.file_name())) {
+                continue;
+            }
+        }
+
+        // Archive members are not allocated on disk by themselves.
+        if config.sparse_only || (config.disk_usage && !config.size_constraints.is_empty()) {
+            continue;
+        }
+        // The contents of directories in archives are not measured, and members are not
+        // tracked by git.
+        if config.dir_filter.is_some() || config.git_filter.is_some() {
+            continue;
+        }
 
-            let entry = match entry_o {
-                Ok(ref e) if e.depth() == 0 => {
-                    // Skip the root directory entry.
-                    return ignore::WalkState::Continue;
+        #[cfg(unix)]
+        {
+            if let Some(ref owner_constraint) = config.owner_constraint {
+                if !member
+                    .owner
+                    .map_or(false, |(uid, gid)| owner_constraint.matches_ids(uid, gid))
+                {
+                    continue;
                 }
-                Ok(e) => DirEntry::normal(e),
-                Err(ignore::Error::WithPath {
-                    path,
-                    err: inner_err,
-                }) => match inner_err.as_ref() {
-                    ignore::Error::Io(io_error)
-                        if io_error.kind() == io::ErrorKind::NotFound
-                            && path
-                                .symlink_metadata()
-                                .ok()
-                                .map_or(false, |m| m.file_type().is_symlink()) =>
-                    {
-                        DirEntry::broken_symlink(path)
-                    }
-                    _ => {
-                        return match tx_thread.send(WorkerResult::Error(ignore::Error::WithPath {
-                            path,
-                            err: inner_err,
-                        })) {
-                            Ok(_) => ignore::WalkState::Continue,
-                            Err(_) => ignore::WalkState::Quit,
-                        }
+            }
+        }
+
+        if !config.time_constraints.is_empty()
+            && !member.modified.map_or(false, |modified| {
+                config
+                    .time_constraints
+                    .iter()
+                    .all(|tf| tf.applies_to(&modified))
+            })
+        {
+            continue;
+        }
+        if !config.size_constraints.is_empty()
+            && (member.kind != archive::MemberKind::File
+                || config
+                    .size_constraints
+                    .iter()
+                    .any(|sc| !sc.is_within(member.size)))
+        {
+            continue;
+        }
+
+        if tx
+            .send(WorkerResult::Entry(PathBuf::from(member_path)))
+            .is_err()
+        {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Whether an archive member, or one of the directories that contain it, is excluded by the
+/// `--exclude` patterns. The archive is treated like a directory that contains its members.
+fn is_excluded_member(overrides: &Override, archive_path: &Path, member: &archive::Member) -> bool {
+    if overrides.is_empty() {
+        return false;
+    }
+    let mut path = archive_path.to_owned();
+    let mut components = member.path.split('/').peekable();
+    while let Some(component) = components.next() {
+        path.push(component);
+        let is_dir = components.peek().is_some() || member.kind == archive::MemberKind::Directory;
+        if overrides.matched(&path, is_dir).is_ignore() {
+            return true;
+        }
+    }
+    false
+}
+
+/// The filter that rejected an entry, see `filter_entry`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rejection {
+    MinDepth,
+    Pattern,
+    ParentPattern,
+    Extension,
+    FileType,
+    Owner,
+    Xattr,
+    Size,
+    Sparse,
+    Directory,
+    Time,
+    Symlink,
+    GitStatus,
+    Kind,
+    Content,
+    Text,
+}
+
+impl Rejection {
+    /// A description of the filter for `--explain`.
+    pub fn description(self) -> &'static str {
+        match self {
+            Rejection::MinDepth => "--min-depth",
+            Rejection::Pattern => "the search pattern",
+            Rejection::ParentPattern => "--parent-pattern",
+            Rejection::Extension => "--extension",
+            Rejection::FileType => "--type",
+            Rejection::Owner => "--owner",
+            Rejection::Xattr => "--xattr/--has-acl",
+            Rejection::Size => "--size",
+            Rejection::Sparse => "--sparse",
+            Rejection::Directory => "--dir-entries/--dir-size",
+            Rejection::Time => "--changed-within/--changed-before",
+            Rejection::Symlink => "--broken-symlinks/--symlink-target/--symlink-escapes",
+            Rejection::GitStatus => "--git-status/--git-ignored-only",
+            Rejection::Kind => "--kind",
+            Rejection::Content => "--mime",
+            Rejection::Text => "--text-only/--binary-only",
+        }
+    }
+}
+
+/// Whether the name of one of the directories between the search path and the entry matches
+/// `pattern`. If the depth of the entry is unknown, all ancestors in `path` are considered.
+fn has_matching_ancestor(pattern: &Regex, path: &Path, depth: Option<usize>) -> bool {
+    let names: Vec<_> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect();
+    let ancestors = &names[..names.len().saturating_sub(1)];
+    let below_root = match depth {
+        Some(depth) => &ancestors[ancestors.len().saturating_sub(depth.saturating_sub(1))..],
+        None => ancestors,
+    };
+    below_root
+        .iter()
+        .any(|name| pattern.is_match(&filesystem::osstr_to_bytes(name)))
+}
+
+/// Apply the search pattern and all filters to an entry that was found by the directory
+/// traversal. Returns the first filter that rejects the entry, if any.
+pub fn filter_entry(config: &Config, pattern: &Regex, entry: &DirEntry) -> Option<Rejection> {
+    if let Some(min_depth) = config.min_depth {
+        if entry.depth().map_or(true, |d| d < min_depth) {
+            return Some(Rejection::MinDepth);
+        }
+    }
+
+    // Check the name first, since it doesn't require metadata
+    let entry_path = entry.path();
+
+    let search_str: Cow<OsStr> = if config.search_full_path {
+        let path_abs_buf =
+            filesystem::path_absolute_form(entry_path).expect("Retrieving absolute path succeeds");
+        Cow::Owned(path_abs_buf.as_os_str().to_os_string())
+    } else if config.search_relative_path {
+        let depth = entry.depth().unwrap_or_else(|| {
+            filesystem::strip_current_dir(entry_path)
+                .components()
+                .count()
+        });
+        Cow::Owned(filesystem::root_relative_path(entry_path, depth))
+    } else {
+        match entry_path.file_name() {
+            Some(filename) => Cow::Borrowed(filename),
+            None => unreachable!(
+                "Encountered file system entry without a file name. This should only \
+                     happen for paths like 'foo/bar/..' or '/' which are not supposed to \
+                     appear in a file system traversal."
+            ),
+        }
+    };
+    let search_str = match config.normalizer {
+        Some(ref normalizer) => Cow::Owned(normalizer.normalize_os(&search_str)),
+        None => search_str,
+    };
+
+    let matched = match config.fuzzy {
+        Some(ref fuzzy) => fuzzy.score(&search_str.to_string_lossy()).is_some(),
+        None => pattern.is_match(&filesystem::osstr_to_bytes(search_str.as_ref())),
+    };
+    if !matched {
+        return Some(Rejection::Pattern);
+    }
+
+    if let Some(ref parent_pattern) = config.parent_pattern {
+        if !has_matching_ancestor(parent_pattern, entry_path, entry.depth()) {
+            return Some(Rejection::ParentPattern);
+        }
+    }
+
+    // Filter out unwanted extensions.
+    if let Some(ref exts_regex) = config.extensions {
+        if let Some(path_str) = entry_path.file_name() {
+            if !exts_regex.is_match(&filesystem::osstr_to_bytes(path_str)) {
+                return Some(Rejection::Extension);
+            }
+        } else {
+            return Some(Rejection::Extension);
+        }
+    }
+
+    // Filter out unwanted file types.
+    if let Some(ref file_types) = config.file_types {
+        if file_types.should_ignore(entry) {
+            return Some(Rejection::FileType);
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        if let Some(ref owner_constraint) = config.owner_constraint {
+            if let Some(metadata) = entry.metadata() {
+                if !owner_constraint.matches(metadata) {
+                    return Some(Rejection::Owner);
+                }
+            } else {
+                return Some(Rejection::Owner);
+            }
+        }
+
+        if let Some(ref xattr_filter) = config.xattr_filter {
+            if !xattr_filter.matches(entry_path) {
+                return Some(Rejection::Xattr);
+            }
+        }
+    }
+
+    // Filter out unwanted sizes if it is a file and we have been given size constraints.
+    if !config.size_constraints.is_empty() {
+        if entry_path.is_file() {
+            if let Some(metadata) = entry.metadata() {
+                let file_size = if config.disk_usage {
+                    filesystem::allocated_size(metadata)
+                } else {
+                    metadata.len()
+                };
+                if config
+                    .size_constraints
+                    .iter()
+                    .any(|sc| !sc.is_within(file_size))
+                {
+                    return Some(Rejection::Size);
+                }
+            } else {
+                return Some(Rejection::Size);
+            }
+        } else {
+            return Some(Rejection::Size);
+        }
+    }
+
+    if config.sparse_only && !entry.metadata().map_or(false, filesystem::is_sparse) {
+        return Some(Rejection::Sparse);
+    }
+
+    if let Some(ref dir_filter) = config.dir_filter {
+        let is_dir = entry.file_type().map_or(false, |ft| ft.is_dir());
+        if !is_dir || !dir_filter.matches(entry_path) {
+            return Some(Rejection::Directory);
+        }
+    }
+
+    // Filter out unwanted modification times
+    if !config.time_constraints.is_empty() {
+        let mut matched = false;
+        if let Some(metadata) = entry.metadata() {
+            if let Ok(modified) = metadata.modified() {
+                matched = config
+                    .time_constraints
+                    .iter()
+                    .all(|tf| tf.applies_to(&modified));
+            }
+        }
+        if !matched {
+            return Some(Rejection::Time);
+        }
+    }
+
+    if let Some(ref symlink_filter) = config.symlink_filter {
+        if !symlink_filter.matches(entry_path) {
+            return Some(Rejection::Symlink);
+        }
+    }
+
+    // Filter by the git status last, since the status of a repository is expensive to
+    // compute the first time.
+    if let Some(ref git_filter) = config.git_filter {
+        if !git_filter.matches(entry_path) {
+            return Some(Rejection::GitStatus);
+        }
+    }
+
+    // Reading the content of a file is the most expensive check, so it comes last. The start of
+    // a regular file is read at most once, by the first filter that needs it.
+    let content = OnceCell::new();
+    let sniff = || {
+        *content.get_or_init(|| {
+            if entry.file_type().map_or(false, |ft| ft.is_file()) {
+                Content::sniff(entry_path).ok()
+            } else {
+                None
+            }
+        })
+    };
+
+    if let Some(ref kind_filter) = config.kind_filter {
+        let name = entry_path.file_name().unwrap_or_default();
+        if !kind_filter.matches(name, sniff) {
+            return Some(Rejection::Kind);
+        }
+    }
+
+    if let Some(ref content_filter) = config.content_filter {
+        if !sniff().map_or(false, |content| content_filter.matches(&content)) {
+            return Some(Rejection::Content);
+        }
+    }
+
+    if let Some(text) = config.text_files {
+        if !sniff().map_or(false, |content| content.is_text == text) {
+            return Some(Rejection::Text);
+        }
+    }
+
+    None
+}
+
+/// Everything that is needed to handle the results of a traversal.
+struct SenderContext<'a> {
+    config: &'a Config,
+    wants_to_quit: &'a AtomicBool,
+    pattern: &'a Regex,
+    /// The '--exclude' patterns of the search path.
+    overrides: &'a Override,
+    includes: Option<&'a Override>,
+    /// The visited directories, and the index of the search path that is traversed.
+    visited: Option<(&'a VisitedEntries, usize)>,
+}
+
+impl SenderContext<'_> {
+    /// Handle a single result of the traversal: errors are forwarded, entries are filtered and
+    /// sent to the receiver if they match. Returns how the traversal should continue.
+    fn handle(
+        &self,
+        entry_o: Result<ignore::DirEntry, ignore::Error>,
+        tx: &Sender<WorkerResult>,
+    ) -> ignore::WalkState {
+        self.handle_below(entry_o, 0, tx)
+    }
+
+    /// Handle a result of a traversal that started `depth_offset` levels below the search path.
+    fn handle_below(
+        &self,
+        entry_o: Result<ignore::DirEntry, ignore::Error>,
+        depth_offset: usize,
+        tx: &Sender<WorkerResult>,
+    ) -> ignore::WalkState {
+        let config = self.config;
+
+        if self.wants_to_quit.load(Ordering::Relaxed) {
+            return ignore::WalkState::Quit;
+        }
+
+        let entry = match entry_o {
+            Ok(e) if e.depth() + depth_offset == 0 => {
+                // Skip the root directory entry, or the whole search path if it has already
+                // been traversed as part of another one.
+                let root = DirEntry::normal(e, 0);
+                return match self.visited {
+                    Some((visited, _)) if !visited.insert_search_path(&root) => {
+                        ignore::WalkState::Skip
                     }
-                },
-                Err(err) => {
-                    return match tx_thread.send(WorkerResult::Error(err)) {
+                    _ => ignore::WalkState::Continue,
+                };
+            }
+            Ok(e) => DirEntry::normal(e, depth_offset),
+            Err(ignore::Error::WithPath {
+                path,
+                err: inner_err,
+            }) => match inner_err.as_ref() {
+                ignore::Error::Io(io_error)
+                    if io_error.kind() == io::ErrorKind::NotFound
+                        && path
+                            .symlink_metadata()
+                            .ok()
+                            .map_or(false, |m| m.file_type().is_symlink()) =>
+                {
+                    DirEntry::broken_symlink(path)
+                }
+                _ => {
+                    return match tx.send(WorkerResult::Error(ignore::Error::WithPath {
+                        path,
+                        err: inner_err,
+                    })) {
                         Ok(_) => ignore::WalkState::Continue,
                         Err(_) => ignore::WalkState::Quit,
                     }
                 }
-            };
+            },
+            Err(err) => {
+                return match tx.send(WorkerResult::Error(err)) {
+                    Ok(_) => ignore::WalkState::Continue,
+                    Err(_) => ignore::WalkState::Quit,
+                }
+            }
+        };
+
+        // Entries excluded by '--exclude-regex' or '--exclude-if' are neither reported nor
+        // traversed.
+        if config.exclusions.iter().any(|e| e.matches(&entry)) {
+            return ignore::WalkState::Skip;
+        }
+
+        // Search paths that have been traversed on their own, and links to directories inside of a
+        // search path, are only reported, but not traversed again.
+        let next_state = match self
+            .visited
+            .map_or(Visit::New, |(visited, root)| visited.insert(&entry, root))
+        {
+            Visit::New => ignore::WalkState::Continue,
+            Visit::SearchPath | Visit::Link => ignore::WalkState::Skip,
+            Visit::Seen => return ignore::WalkState::Skip,
+        };
+
+        let entry_path = entry.path();
+
+        // The traversal for '--include' only reports entries that match one of its patterns.
+        if let Some(includes) = self.includes {
+            let is_dir = entry.file_type().map_or(false, |ft| ft.is_dir());
+            if !includes.matched(entry_path, is_dir).is_whitelist() {
+                return next_state;
+            }
+        }
 
-            if let Some(min_depth) = config.min_depth {
-                if entry.depth().map_or(true, |d| d < min_depth) {
-                    return ignore::WalkState::Continue;
+        // The members of an archive are searched independently of whether the archive
+        // itself is a match.
+        if config.search_archives && entry.file_type().map_or(false, |ft| ft.is_file()) {
+            if let Some(format) = ArchiveFormat::from_path(entry_path) {
+                if !search_archive(
+                    config,
+                    self.pattern,
+                    &entry,
+                    format,
+                    Some(self.overrides),
+                    tx,
+                ) {
+                    return ignore::WalkState::Quit;
                 }
             }
+        }
 
-            // Check the name first, since it doesn't require metadata
-            let entry_path = entry.path();
+        if filter_entry(config, self.pattern, &entry).is_some() {
+            return next_state;
+        }
 
-            let search_str: Cow<OsStr> = if config.search_full_path {
-                let path_abs_buf = filesystem::path_absolute_form(entry_path)
-                    .expect("Retrieving absolute path succeeds");
-                Cow::Owned(path_abs_buf.as_os_str().to_os_string())
-            } else {
-                match entry_path.file_name() {
-                    Some(filename) => Cow::Borrowed(filename),
-                    None => unreachable!(
-                        "Encountered file system entry without a file name. This should only \
-                         happen for paths like 'foo/bar/..' or '/' which are not supposed to \
-                         appear in a file system traversal."
-                    ),
+        let send_result = tx.send(WorkerResult::Entry(entry_path.to_owned()));
+
+        if send_result.is_err() {
+            return ignore::WalkState::Quit;
+        }
+
+        // Apply pruning. Directories that are deleted recursively do not need to be traversed.
+        if config.prune
+            || (config.delete_recursive && entry.file_type().map_or(false, |ft| ft.is_dir()))
+        {
+            return ignore::WalkState::Skip;
+        }
+
+        next_state
+    }
+}
+
+fn spawn_senders(context: &SenderContext, mut walker: WalkBuilder, tx: Sender<WorkerResult>) {
+    let config = context.config;
+    match config.traversal {
+        None => {
+            walker.threads(config.threads).build_parallel().run(|| {
+                let tx_thread = tx.clone();
+                Box::new(move |entry_o| context.handle(entry_o, &tx_thread))
+            });
+        }
+        Some(traversal) => walk_ordered(context, walker, traversal, &tx),
+    }
+}
+
+/// Whether `path` is a directory, following symbolic links only with `--follow`.
+fn is_directory(path: &Path, follow_links: bool) -> bool {
+    if follow_links {
+        path.is_dir()
+    } else {
+        path.symlink_metadata().map_or(false, |m| m.is_dir())
+    }
+}
+
+/// Sort the entries of each directory by their name, and place the directories first or last
+/// (`--dirs-first`, `--dirs-last`).
+fn sort_entries(walker: &mut WalkBuilder, config: &Config) {
+    // The sort order only depends on the file type of the entries of one directory, which are
+    // looked up once and remembered while that directory is sorted.
+    let follow_links = config.follow_links;
+    let directory_order = config.directory_order;
+    let file_types = Mutex::new((PathBuf::new(), HashMap::<PathBuf, bool>::new()));
+    walker.sort_by_file_path(move |a, b| {
+        if directory_order.is_none() {
+            return a.file_name().cmp(&b.file_name());
+        }
+        let rank = |path: &Path| {
+            let is_dir = {
+                let mut file_types = file_types.lock().unwrap();
+                let (ref mut parent, ref mut is_dir) = *file_types;
+                if path.parent() != Some(parent.as_path()) {
+                    *parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                    is_dir.clear();
                 }
+                *is_dir
+                    .entry(path.to_owned())
+                    .or_insert_with(|| is_directory(path, follow_links))
             };
-
-            if !pattern.is_match(&filesystem::osstr_to_bytes(search_str.as_ref())) {
-                return ignore::WalkState::Continue;
+            match directory_order {
+                Some(DirectoryOrder::Last) => is_dir,
+                _ => !is_dir,
             }
+        };
+        rank(a)
+            .cmp(&rank(b))
+            .then_with(|| a.file_name().cmp(&b.file_name()))
+    });
+}
 
-            // Filter out unwanted extensions.
-            if let Some(ref exts_regex) = config.extensions {
-                if let Some(path_str) = entry_path.file_name() {
-                    if !exts_regex.is_match(&filesystem::osstr_to_bytes(path_str)) {
-                        return ignore::WalkState::Continue;
+/// Traverse a search path on a single thread, with the entries of each directory sorted by their
+/// name (`--traversal`, `--dirs-first`, `--dirs-last`).
+///
+/// For a depth-first traversal, the sequential walker cannot be told to skip a directory after it
+/// has been returned. Instead, skipped directories are remembered, and their entries are dropped
+/// by a filter before the walker descends any further. For a breadth-first traversal, the
+/// directories are queued and read one at a time, so that skipped directories are never opened.
+fn walk_ordered(
+    context: &SenderContext,
+    mut walker: WalkBuilder,
+    traversal: Traversal,
+    tx: &Sender<WorkerResult>,
+) {
+    let config = context.config;
+    sort_entries(&mut walker, config);
+
+    match traversal {
+        Traversal::DepthFirst => {
+            let skipped = Arc::new(Mutex::new(HashSet::<PathBuf>::new()));
+            let skipped_filter = Arc::clone(&skipped);
+            walker.filter_entry(move |entry| {
+                entry.path().parent().map_or(true, |parent| {
+                    !skipped_filter.lock().unwrap().contains(parent)
+                })
+            });
+
+            for result in walker.build() {
+                let directory = match result {
+                    Ok(ref entry) if entry.file_type().map_or(false, |ft| ft.is_dir()) => {
+                        Some(entry.path().to_owned())
                     }
-                } else {
-                    return ignore::WalkState::Continue;
+                    _ => None,
+                };
+                match context.handle(result, tx) {
+                    ignore::WalkState::Continue => {}
+                    ignore::WalkState::Skip => skipped.lock().unwrap().extend(directory),
+                    ignore::WalkState::Quit => return,
                 }
             }
-
-            // Filter out unwanted file types.
-            if let Some(ref file_types) = config.file_types {
-                if file_types.should_ignore(&entry) {
-                    return ignore::WalkState::Continue;
+        }
+        Traversal::BreadthFirst => {
+            let is_within_max_depth =
+                |depth: usize| config.max_depth.map_or(true, |max_depth| depth < max_depth);
+
+            // Only the search path itself is taken from the given walker.
+            let mut root = None;
+            for result in walker.max_depth(Some(0)).build() {
+                if let Ok(ref entry) = result {
+                    root = Some(entry.path().to_owned());
+                }
+                match context.handle(result, tx) {
+                    ignore::WalkState::Continue => {}
+                    ignore::WalkState::Skip | ignore::WalkState::Quit => return,
                 }
             }
+            let root = match root {
+                Some(root) => root,
+                None => return,
+            };
 
-            #[cfg(unix)]
-            {
-                if let Some(ref owner_constraint) = config.owner_constraint {
-                    if let Some(metadata) = entry.metadata() {
-                        if !owner_constraint.matches(metadata) {
-                            return ignore::WalkState::Continue;
+            let mut queue = VecDeque::new();
+            if is_within_max_depth(0) {
+                queue.push_back((root.clone(), 0));
+            }
+            while let Some((directory, depth)) = queue.pop_front() {
+                for result in directory_walker(context, &root, &directory).build() {
+                    if matches!(result, Ok(ref entry) if entry.depth() == 0) {
+                        continue;
+                    }
+                    let subdirectory = match result {
+                        Ok(ref entry) if entry.file_type().map_or(false, |ft| ft.is_dir()) => {
+                            Some(entry.path().to_owned())
+                        }
+                        _ => None,
+                    };
+                    match context.handle_below(result, depth, tx) {
+                        ignore::WalkState::Continue if is_within_max_depth(depth + 1) => {
+                            queue.extend(subdirectory.map(|path| (path, depth + 1)));
                         }
-                    } else {
-                        return ignore::WalkState::Continue;
+                        ignore::WalkState::Continue | ignore::WalkState::Skip => {}
+                        ignore::WalkState::Quit => return,
                     }
                 }
             }
+        }
+    }
+}
 
-            // Filter out unwanted sizes if it is a file and we have been given size constraints.
-            if !config.size_constraints.is_empty() {
-                if entry_path.is_file() {
-                    if let Some(metadata) = entry.metadata() {
-                        let file_size = metadata.len();
-                        if config
-                            .size_constraints
-                            .iter()
-                            .any(|sc| !sc.is_within(file_size))
-                        {
-                            return ignore::WalkState::Continue;
-                        }
-                    } else {
-                        return ignore::WalkState::Continue;
-                    }
-                } else {
-                    return ignore::WalkState::Continue;
+/// A walker that only reads the entries of `directory` below the search path `root`, with the
+/// same ignore rules as a traversal of the whole search path.
+fn directory_walker(context: &SenderContext, root: &Path, directory: &Path) -> WalkBuilder {
+    let config = context.config;
+    let mut walker = build_walker(directory, context.overrides.clone(), config, false);
+    walker.max_depth(Some(1));
+    sort_entries(&mut walker, config);
+
+    // The ignore files between the search path and the directory still apply if the ones in
+    // the parent directories of the walker are not read.
+    if !config.read_parent_ignore {
+        let mut names = Vec::new();
+        if config.read_vcsignore {
+            names.push(".gitignore");
+        }
+        if config.read_fdignore {
+            names.push(".ignore");
+            names.push(".fdignore");
+        }
+        for ancestor in directory
+            .ancestors()
+            .skip(1)
+            .take_while(|ancestor| ancestor.starts_with(root))
+        {
+            for name in &names {
+                let file = ancestor.join(name);
+                if file.is_file() {
+                    walker.add_ignore(file);
                 }
             }
+        }
+    }
 
-            // Filter out unwanted modification times
-            if !config.time_constraints.is_empty() {
-                let mut matched = false;
-                if let Some(metadata) = entry.metadata() {
-                    if let Ok(modified) = metadata.modified() {
-                        matched = config
-                            .time_constraints
-                            .iter()
-                            .all(|tf| tf.applies_to(&modified));
+    walker
+}
+
+/// Report every directory after its contents (`--dirs-last` with depth-first traversal). The
+/// results have to arrive in depth-first order, with every directory before its contents.
+fn contents_first(rx: Receiver<WorkerResult>, follow_links: bool) -> Receiver<WorkerResult> {
+    let (tx, reordered_rx) = channel();
+    thread::spawn(move || {
+        let mut directories: Vec<PathBuf> = Vec::new();
+        for result in rx {
+            if let WorkerResult::Entry(ref path) = result {
+                while let Some(directory) = directories.pop() {
+                    if path.starts_with(&directory) {
+                        directories.push(directory);
+                        break;
+                    }
+                    if tx.send(WorkerResult::Entry(directory)).is_err() {
+                        return;
                     }
                 }
-                if !matched {
-                    return ignore::WalkState::Continue;
+                if is_directory(path, follow_links) {
+                    directories.push(path.clone());
+                    continue;
                 }
             }
-
-            let send_result = tx_thread.send(WorkerResult::Entry(entry_path.to_owned()));
-
-            if send_result.is_err() {
-                return ignore::WalkState::Quit;
+            if tx.send(result).is_err() {
+                return;
             }
-
-            // Apply pruning.
-            if config.prune {
-                return ignore::WalkState::Skip;
+        }
+        while let Some(directory) = directories.pop() {
+            if tx.send(WorkerResult::Entry(directory)).is_err() {
+                return;
             }
-
-            ignore::WalkState::Continue
-        })
+        }
     });
+    reordered_rx
 }
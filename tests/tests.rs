@@ -671,8 +671,14 @@ fn test_follow() {
     te.assert_output(
         &["--follow", "c.foo"],
         "one/two/c.foo
-        one/two/C.Foo2
-        symlink/c.foo
+        one/two/C.Foo2",
+    );
+
+    // The contents of a symlinked directory are found through the real path, unless the link
+    // is the search path itself.
+    te.assert_output(
+        &["--follow", "c.foo", "symlink"],
+        "symlink/c.foo
         symlink/C.Foo2",
     );
 }
@@ -990,6 +996,7 @@ fn test_type_executable() {
 
     fs::OpenOptions::new()
         .create(true)
+        .truncate(true)
         .write(true)
         .mode(0o777)
         .open(te.test_root().join("executable-file.sh"))
@@ -1901,3 +1908,1506 @@ fn test_error_if_hidden_not_set_and_pattern_starts_with_dot() {
     te.assert_output(&["--hidden", "--glob", ".gitignore"], ".gitignore");
     te.assert_output(&[".gitignore"], "");
 }
+
+/// Renaming search results (--rename)
+#[test]
+fn test_rename() {
+    let te = TestEnv::new(
+        &["photos"],
+        &["photos/cat.jpeg", "photos/dog.jpeg", "photos/bird.jpg"],
+    );
+    let journal = te.test_root().join("journal.sh");
+    let journal = journal.to_str().unwrap();
+
+    te.assert_output(
+        &["-e", "jpeg", "--rename", "{/.}.jpg", "--dry-run"],
+        "photos/cat.jpeg  ->  photos/cat.jpg
+         photos/dog.jpeg  ->  photos/dog.jpg",
+    );
+    te.assert_output(
+        &["-e", "jpeg"],
+        "photos/cat.jpeg
+         photos/dog.jpeg",
+    );
+
+    te.assert_output(
+        &[
+            "-e",
+            "jpeg",
+            "--rename",
+            "{/.}.jpg",
+            "--rename-journal",
+            journal,
+        ],
+        "photos/cat.jpeg  ->  photos/cat.jpg
+         photos/dog.jpeg  ->  photos/dog.jpg",
+    );
+    te.assert_output(
+        &["-e", "jpg"],
+        "photos/bird.jpg
+         photos/cat.jpg
+         photos/dog.jpg",
+    );
+    assert!(Path::new(journal).is_file());
+
+    // Revert the renames with the undo journal
+    #[cfg(unix)]
+    {
+        let status = std::process::Command::new("sh")
+            .arg(journal)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        te.assert_output(
+            &["-e", "jpeg"],
+            "photos/cat.jpeg
+             photos/dog.jpeg",
+        );
+    }
+}
+
+/// Collisions and cycles in --rename
+#[test]
+fn test_rename_collisions() {
+    let te = TestEnv::new(&[], &["a-b.txt", "b-a.txt", "c-d.txt", "x.txt"]);
+    let journal = te.test_root().join("journal.sh");
+    let journal = journal.to_str().unwrap();
+    fs::write(te.test_root().join("a-b.txt"), "first").unwrap();
+
+    // Two entries would get the same name
+    te.assert_failure_with_error(
+        &["txt$", "--rename", "same.txt", "--rename-journal", journal],
+        "[fd error]: Both",
+    );
+
+    // An existing file would be overwritten
+    te.assert_failure_with_error(
+        &["^c", "--rename", "x.txt", "--rename-journal", journal],
+        "[fd error]: Renaming 'c-d.txt' would overwrite the existing 'x.txt'.
+         [fd error]: Nothing has been renamed.",
+    );
+    te.assert_output(
+        &["txt$"],
+        "a-b.txt
+         b-a.txt
+         c-d.txt
+         x.txt",
+    );
+
+    // Swapping names with capture groups is a cycle, which is resolved automatically
+    te.assert_output(
+        &[
+            r"^(\w)-(\w)\.txt$",
+            "--rename",
+            "{2}-{1}.txt",
+            "--rename-journal",
+            journal,
+        ],
+        "a-b.txt  ->  b-a.txt
+         b-a.txt  ->  a-b.txt
+         c-d.txt  ->  d-c.txt",
+    );
+    te.assert_output(
+        &["txt$"],
+        "a-b.txt
+         b-a.txt
+         d-c.txt
+         x.txt",
+    );
+    assert_eq!(
+        fs::read_to_string(te.test_root().join("b-a.txt")).unwrap(),
+        "first"
+    );
+
+    // Unknown capture groups are rejected
+    te.assert_failure(&["--rename", "{1}"]);
+}
+
+/// Deleting search results (--delete)
+#[test]
+fn test_delete() {
+    let te = TestEnv::new(
+        &["build/cache", "build/empty", "src"],
+        &["build/cache/a.o", "build/b.o", "src/main.c", "src/main.o"],
+    );
+
+    te.assert_output(
+        &["--delete", "--dry-run", "-e", "o"],
+        "build/cache/a.o
+         build/b.o
+         src/main.o",
+    );
+    te.assert_output(
+        &["-e", "o"],
+        "build/cache/a.o
+         build/b.o
+         src/main.o",
+    );
+
+    te.assert_output(&["--delete", "-e", "o"], "");
+    te.assert_output(&["-e", "o"], "");
+
+    // Only empty directories are deleted without --recursive
+    te.assert_failure_with_error(
+        &["--delete", "^build$"],
+        "[fd error]: Could not delete 'build'",
+    );
+    te.assert_output(&["--delete", "^empty$"], "");
+    te.assert_output(&["", "build"], "build/cache");
+
+    te.assert_output(&["--delete", "--recursive", "^build$"], "");
+    te.assert_output(&["", "--type", "d"], "src");
+
+    // Search paths are never deleted
+    te.assert_failure_with_error(
+        &["--delete", "^src$", ".", "src"],
+        "[fd error]: Refusing to delete 'src', as it contains a search root.",
+    );
+    te.assert_output(&["main.c"], "src/main.c");
+}
+
+/// Copying and moving search results (--copy-to, --move-to)
+#[test]
+fn test_copy_move() {
+    let te = TestEnv::new(
+        &["docs/drafts", "src"],
+        &["docs/a.pdf", "docs/drafts/b.pdf", "src/main.c"],
+    );
+
+    te.assert_output(
+        &["--copy-to", "backup", "--dry-run", "-e", "pdf", ".", "docs"],
+        "docs/a.pdf         ->  backup/a.pdf
+         docs/drafts/b.pdf  ->  backup/drafts/b.pdf",
+    );
+
+    te.assert_output(&["--copy-to", "backup", "-e", "pdf", ".", "docs"], "");
+    te.assert_output(
+        &["-e", "pdf"],
+        "backup/a.pdf
+         backup/drafts/b.pdf
+         docs/a.pdf
+         docs/drafts/b.pdf",
+    );
+
+    // Existing files are never overwritten
+    te.assert_failure_with_error(
+        &["--copy-to", "backup", "^a.pdf$", "docs"],
+        "[fd error]: Could not copy 'docs/a.pdf' to 'backup/a.pdf': the target already exists",
+    );
+
+    // Entries inside of the destination are skipped
+    te.assert_output(
+        &["--move-to", "archive", "--dry-run", "-e", "c"],
+        "src/main.c  ->  archive/src/main.c",
+    );
+    te.assert_output(&["--move-to", "archive", "^src$|main.c"], "");
+    te.assert_output(&["", "archive"], "archive/src\narchive/src/main.c");
+    te.assert_output(&["^src$"], "archive/src");
+}
+
+/// Writing search results to an archive (--archive)
+#[test]
+fn test_archive() {
+    let te = TestEnv::new(
+        &["src/nested"],
+        &["src/a.rs", "src/nested/b.rs", "README.md"],
+    );
+    let root = te.test_root();
+
+    te.assert_output(
+        &["--archive", "out.tar", "--dry-run", "-e", "rs", ".", "src"],
+        "src/a.rs  ->  a.rs
+         src/nested/b.rs  ->  nested/b.rs",
+    );
+    assert!(!root.join("out.tar").exists());
+
+    te.assert_output(&["--archive", "out.tar.gz", "-e", "rs", "-e", "gz"], "");
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(
+        fs::File::open(root.join("out.tar.gz")).unwrap(),
+    ));
+    let mut members: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            entry
+                .unwrap()
+                .path()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    members.sort();
+    assert_eq!(members, vec!["src/a.rs", "src/nested/b.rs"]);
+
+    te.assert_output(&["--archive", "out.zip", "", "src"], "");
+    let mut archive = zip::ZipArchive::new(fs::File::open(root.join("out.zip")).unwrap()).unwrap();
+    let mut members: Vec<String> = archive.file_names().map(String::from).collect();
+    members.sort();
+    assert_eq!(members, vec!["a.rs", "nested/", "nested/b.rs"]);
+
+    let mut contents = String::new();
+    std::io::Read::read_to_string(&mut archive.by_name("a.rs").unwrap(), &mut contents).unwrap();
+    assert_eq!(contents, "");
+
+    te.assert_failure_with_error(
+        &["--archive", "out.rar", "."],
+        "[fd error]: Unknown archive format of 'out.rar'. Use one of the extensions '.tar', \
+         '.tar.gz', '.tgz' or '.zip'.",
+    );
+}
+
+/// Searching the entries of archives (--search-archives)
+#[test]
+fn test_search_archives() {
+    let te = TestEnv::new(&["releases"], &["releases/libfoo.so", "releases/notes.txt"]);
+    let root = te.test_root();
+
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        fs::File::create(root.join("releases/v1.tar.gz")).unwrap(),
+        flate2::Compression::default(),
+    ));
+    for (name, contents) in &[
+        ("./lib/libfoo.so", "x".repeat(2000)),
+        ("./README", String::new()),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, contents.as_bytes())
+            .unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(root.join("releases/v2.zip")).unwrap());
+    zip.add_directory("lib", zip::write::FileOptions::default())
+        .unwrap();
+    zip.start_file("lib/libfoo.so", zip::write::FileOptions::default())
+        .unwrap();
+    zip.write_all(b"foo").unwrap();
+    zip.finish().unwrap();
+
+    te.assert_output(&["libfoo"], "releases/libfoo.so");
+    te.assert_output(
+        &["--search-archives", "libfoo"],
+        "releases/libfoo.so
+         releases/v1.tar.gz!/lib/libfoo.so
+         releases/v2.zip!/lib/libfoo.so",
+    );
+    te.assert_output(
+        &["--search-archives", "--type", "d"],
+        "releases
+         releases/v2.zip!/lib",
+    );
+    te.assert_output(
+        &["--search-archives", "--size", "+1k", "--type", "f"],
+        "releases/v1.tar.gz!/lib/libfoo.so",
+    );
+    te.assert_output(
+        &["--search-archives", "--full-path", r"\.zip!/lib/"],
+        "releases/v2.zip!/lib/libfoo.so",
+    );
+}
+
+/// Filtering by git status (--git-status, --git-ignored-only)
+#[test]
+fn test_git_status() {
+    let te = TestEnv::new(
+        &["src", "target"],
+        &[
+            "src/lib.rs",
+            "src/main.rs",
+            "src/new.rs",
+            "target/out.rs",
+            "README.md",
+        ],
+    );
+    let root = te.test_root();
+
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(["-c", "user.name=fd", "-c", "user.email=fd@example.com"])
+            .args(args)
+            .status()
+            .expect("git is installed");
+        assert!(status.success());
+    };
+
+    // Replace the fake repository of the test environment with a real one.
+    fs::remove_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join(".gitignore"), "target/\n").unwrap();
+    git(&["init", "-q"]);
+    git(&[
+        "add",
+        ".gitignore",
+        "README.md",
+        "src/lib.rs",
+        "src/main.rs",
+    ]);
+    git(&["commit", "-q", "-m", "initial"]);
+
+    fs::write(root.join("src/lib.rs"), "// changed").unwrap();
+    fs::write(root.join("README.md"), "changed").unwrap();
+    git(&["add", "README.md"]);
+
+    te.assert_output(&["--git-status=modified", "-e", "rs"], "src/lib.rs");
+    te.assert_output(&["--git-status", "modified", "-e", "rs"], "src/lib.rs");
+    te.assert_output(
+        &["--git-status=untracked"],
+        "src
+         src/new.rs
+         symlink",
+    );
+    te.assert_output(&["--git-status=staged"], "README.md");
+    te.assert_output(
+        &["--git-status=modified,untracked", "--type", "f"],
+        "src/lib.rs
+         src/new.rs",
+    );
+    te.assert_output(&["--git-status=modified", "--type", "d"], "src");
+    te.assert_output(&["--git-status=conflicted"], "");
+
+    te.assert_output(
+        &["--git-ignored-only"],
+        "target
+         target/out.rs",
+    );
+
+    te.assert_failure_with_error(
+        &["--git-status=changed"],
+        "[fd error]: 'changed' is not a valid git status. Use one of 'modified', 'untracked', \
+         'staged' or 'conflicted'.",
+    );
+}
+
+/// Explaining why a path is (not) part of the search results (--explain)
+#[test]
+fn test_explain() {
+    let te = TestEnv::new(
+        &["src/.cache", "target/debug"],
+        &[
+            "src/main.rs",
+            "src/a.o",
+            "src/keep.o",
+            "src/.cache/x",
+            "target/debug/fd",
+        ],
+    );
+    fs::write(te.test_root().join(".gitignore"), "target/\n*.o\n!keep.o\n").unwrap();
+
+    te.assert_output(
+        &["--explain", "target/debug/fd"],
+        "path:     target/debug/fd
+         search:   .
+         hidden:   no
+         ignored:  'target' is ignored by 'target/' in '.gitignore', line 1
+         excluded: no
+         filters:  not checked
+         result:   not shown",
+    );
+    te.assert_output(
+        &["--explain", "src/keep.o"],
+        "path:     src/keep.o
+         search:   .
+         hidden:   no
+         ignored:  'src/keep.o' is whitelisted by '!keep.o' in '.gitignore', line 3
+         excluded: no
+         filters:  all passed
+         result:   shown",
+    );
+    te.assert_output(
+        &["--explain", "src/.cache/x"],
+        "path:     src/.cache/x
+         search:   .
+         hidden:   'src/.cache' is hidden (use '--hidden' to show it)
+         ignored:  no ignore rule matches
+         excluded: no
+         filters:  not checked
+         result:   not shown",
+    );
+    te.assert_output(
+        &["--exclude", "*.rs", "--explain", "src/main.rs"],
+        "path:     src/main.rs
+         search:   .
+         hidden:   no
+         ignored:  no ignore rule matches
+         excluded: 'src/main.rs' matches '--exclude *.rs'
+         filters:  not checked
+         result:   not shown",
+    );
+    te.assert_output(
+        &["--extension", "c", "--explain", "src/main.rs", ".", "src"],
+        "path:     src/main.rs
+         search:   src
+         hidden:   no
+         ignored:  no ignore rule matches
+         excluded: no
+         filters:  'src/main.rs' is rejected by --extension
+         result:   not shown",
+    );
+
+    te.assert_failure_with_error(
+        &["--explain", "missing.rs"],
+        "[fd error]: 'missing.rs' does not exist.",
+    );
+}
+
+/// Re-including hidden and ignored entries (--include)
+#[test]
+fn test_include() {
+    let te = TestEnv::new(
+        &["src", "target/debug/deps"],
+        &[
+            "src/main.rs",
+            "src/.env",
+            "target/debug/deps/a.d",
+            "target/debug/deps/a.rlib",
+        ],
+    );
+    fs::write(te.test_root().join(".gitignore"), "debug/\n").unwrap();
+
+    te.assert_output(&["", "src", "target"], "src/main.rs");
+    te.assert_output(
+        &[
+            "--include",
+            "**/*.d",
+            "--include",
+            ".env",
+            "",
+            "src",
+            "target",
+        ],
+        "src/.env
+         src/main.rs
+         target/debug/deps/a.d",
+    );
+
+    // Entries are only reported once
+    te.assert_output(&["--include", "*.rs", "", "src"], "src/main.rs");
+
+    // The search pattern and other filters still apply
+    te.assert_output(
+        &["--include", "**/deps/*", "--type", "f", "rlib$", "target"],
+        "target/debug/deps/a.rlib",
+    );
+
+    // --exclude takes precedence
+    te.assert_output(&["--include", "*.d", "--exclude", "deps", "", "target"], "");
+}
+
+/// Multiple search paths (exclude patterns, overlapping paths and --format)
+#[test]
+fn test_multiple_search_paths() {
+    let te = TestEnv::new(
+        &["a/sub/x", "b/sub/x"],
+        &["a/sub/x/f1", "a/sub/f2", "b/sub/x/f3", "b/f4"],
+    );
+
+    // Exclude patterns are relative to each search path
+    te.assert_output(
+        &["--exclude", "sub/x", "", "a", "b"],
+        "a/sub
+         a/sub/f2
+         b/f4
+         b/sub",
+    );
+
+    // Overlapping search paths do not lead to duplicate results
+    te.assert_output(
+        &["--type", "f", "", "a", "a/sub", "a/sub/x"],
+        "a/sub/f2
+         a/sub/x/f1",
+    );
+
+    te.assert_output(
+        &["--type", "d", "", "a/sub", "a"],
+        "a/sub
+         a/sub/x",
+    );
+
+    te.assert_output(
+        &["--type", "f", "--format", "{root}: {/}", "", "a/sub", "b"],
+        "a/sub: f1
+         a/sub: f2
+         b: f3
+         b: f4",
+    );
+
+    te.assert_failure_with_error(
+        &["--format", "{bytes}", ""],
+        "[fd error]: Unknown placeholder '{bytes}' in the format template.",
+    );
+}
+
+/// Filtering a given list of paths (--paths-from) and reading search paths (--roots-from)
+#[test]
+fn test_paths_from() {
+    let te = TestEnv::new(DEFAULT_DIRS, DEFAULT_FILES);
+    fs::write(
+        te.test_root().join("list.txt"),
+        "a.foo\none/b.foo\r\n\none/two\n.hidden.foo\nmissing.foo\n",
+    )
+    .unwrap();
+    fs::write(
+        te.test_root().join("list0.txt"),
+        "one/two/c.foo\0one/two/three\0e1 e2\0",
+    )
+    .unwrap();
+
+    te.assert_output(
+        &["--paths-from", "list.txt"],
+        ".hidden.foo
+         a.foo
+         one/b.foo
+         one/two",
+    );
+    te.assert_output(
+        &["--paths-from", "list.txt", "--type", "f", "^[ab]"],
+        "a.foo
+         one/b.foo",
+    );
+    te.assert_output(
+        &["--paths-from", "list.txt", "--min-depth", "2"],
+        "one/b.foo\none/two",
+    );
+    te.assert_output(
+        &["--paths-from", "list0.txt"],
+        "e1 e2
+         one/two/c.foo
+         one/two/three",
+    );
+
+    te.assert_output(
+        &["--roots-from", "list0.txt", "foo"],
+        "one/two/three/d.foo
+         one/two/three/directory_foo",
+    );
+
+    te.assert_failure_with_error(
+        &["--paths-from", "nonexistent.txt"],
+        "[fd error]: Could not read the list of paths from 'nonexistent.txt'",
+    );
+}
+
+/// Fuzzy search with ranked results (--fuzzy)
+#[test]
+fn test_fuzzy() {
+    let te = TestEnv::new(
+        &["src/fuzzy", "tests"],
+        &[
+            "src/main.rs",
+            "src/my_animation.rs",
+            "src/fuzzy/mod.rs",
+            "tests/main_test.rs",
+            "Makefile",
+        ],
+    );
+
+    te.assert_output(
+        &["--fuzzy", "main"],
+        "src/main.rs
+         tests/main_test.rs
+         src/my_animation.rs",
+    );
+    te.assert_output(&["--fuzzy", "--max-results", "1", "main"], "src/main.rs");
+
+    // Smart case
+    te.assert_output(&["--fuzzy", "mkf"], "Makefile");
+    te.assert_output(&["--fuzzy", "MKF"], "");
+
+    // Other filters still apply
+    te.assert_output(&["--fuzzy", "--type", "d", "fz"], "src/fuzzy");
+
+    // Match against the full path
+    te.assert_output(
+        &["--fuzzy", "--full-path", "--type", "f", "fzmod"],
+        "src/fuzzy/mod.rs",
+    );
+}
+
+/// Filter by the names of ancestor directories (--parent-pattern)
+#[test]
+fn test_parent_pattern() {
+    let te = TestEnv::new(
+        &["deploy/k8s/base", "k8s", "docs/K8S"],
+        &[
+            "deploy/k8s/base/service.yaml",
+            "deploy/k8s/kustomization.yaml",
+            "deploy/compose.yaml",
+            "k8s/notes.yaml",
+            "docs/K8S/intro.yaml",
+        ],
+    );
+
+    te.assert_output(
+        &["--parent-pattern", "^k8s$", "-e", "yaml"],
+        "deploy/k8s/base/service.yaml
+         deploy/k8s/kustomization.yaml
+         docs/K8S/intro.yaml
+         k8s/notes.yaml",
+    );
+
+    // The search path itself is not considered
+    te.assert_output_subdirectory("k8s", &["--parent-pattern", "k8s", "-e", "yaml"], "");
+    te.assert_output(
+        &["--parent-pattern", "^k8s$", "-e", "yaml", "", "deploy/k8s"],
+        "",
+    );
+
+    // Smart case
+    te.assert_output(
+        &["--parent-pattern", "K8S", "-e", "yaml"],
+        "docs/K8S/intro.yaml",
+    );
+
+    // Directories are matched as well
+    te.assert_output(
+        &["--parent-pattern", "deploy", "--type", "d"],
+        "deploy/k8s\ndeploy/k8s/base",
+    );
+}
+
+/// Multiple search patterns (--pattern, --patterns-from)
+#[test]
+fn test_multiple_patterns() {
+    let te = TestEnv::new(
+        &["keys", "src"],
+        &["keys/id_rsa", "keys/server.pem", "src/main.rs", "src/.env"],
+    );
+    fs::write(
+        te.test_root().join("forbidden.txt"),
+        "^id_rsa$\n\n\\.env$\n",
+    )
+    .unwrap();
+
+    te.assert_output(
+        &["--pattern", "pem$", "--pattern", "^id_"],
+        "keys/id_rsa
+         keys/server.pem",
+    );
+
+    // All positional arguments are search paths
+    te.assert_output(&["--pattern", "rs$", "src", "keys"], "src/main.rs");
+
+    te.assert_output(
+        &["--hidden", "--patterns-from", "forbidden.txt"],
+        "keys/id_rsa
+         src/.env",
+    );
+    te.assert_output(
+        &[
+            "--hidden",
+            "--patterns-from",
+            "forbidden.txt",
+            "--pattern",
+            "pem",
+        ],
+        "keys/id_rsa
+         keys/server.pem
+         src/.env",
+    );
+
+    te.assert_output(
+        &["--glob", "--pattern", "*.pem", "--pattern", "*.rs"],
+        "keys/server.pem
+         src/main.rs",
+    );
+
+    te.assert_output(
+        &[
+            "--hidden",
+            "--patterns-from",
+            "forbidden.txt",
+            "--format",
+            "{pattern} {}",
+        ],
+        "^id_rsa$ keys/id_rsa
+         \\.env$ src/.env",
+    );
+}
+
+/// Exclusion by regex and by condition (--exclude-regex, --exclude-if)
+#[test]
+fn test_exclude_regex_and_condition() {
+    let te = TestEnv::new(DEFAULT_DIRS, DEFAULT_FILES);
+
+    te.assert_output(
+        &["--exclude-regex", "^[a-c]\\.", "foo"],
+        "one/two/three/d.foo
+        one/two/three/directory_foo",
+    );
+
+    // Excluded directories are not traversed
+    te.assert_output(&["--exclude-regex", "^two$", "foo"], "a.foo\none/b.foo");
+
+    // Regexes with a path separator are matched against the full path
+    te.assert_output(
+        &["--exclude-regex", "one/two/.*foo$", "foo"],
+        "a.foo
+        one/b.foo
+        one/two/C.Foo2",
+    );
+
+    te.assert_output(&["--exclude-if", "type=d", "foo"], "a.foo");
+    te.assert_output(
+        &["--exclude-if", "type=f,size=-0B", "--type", "f", "foo"],
+        "",
+    );
+
+    te.assert_failure(&["--exclude-if", "color=red"]);
+}
+
+/// Globs on the path relative to the search path (--path-glob)
+#[test]
+fn test_path_glob() {
+    let te = TestEnv::new(
+        &["src/exec", "tests/data", "lib/src"],
+        &[
+            "src/main.rs",
+            "src/exec/mod.rs",
+            "tests/tests.rs",
+            "tests/data/a.json",
+            "tests/data/b.yaml",
+            "lib/src/lib.rs",
+        ],
+    );
+
+    te.assert_output(
+        &["--path-glob", "src/**/*.rs"],
+        "src/main.rs
+        src/exec/mod.rs",
+    );
+    te.assert_output(&["--path-glob", "/src/*.rs"], "src/main.rs");
+
+    // Without a separator, the pattern matches the file name at any depth
+    te.assert_output(
+        &["--path-glob", "*.{json,yaml}"],
+        "tests/data/a.json
+        tests/data/b.yaml",
+    );
+
+    // Patterns are relative to each search path
+    te.assert_output(&["--path-glob", "src/*.rs", "", "lib"], "lib/src/lib.rs");
+    te.assert_output(
+        &["--path-glob", "[ab].*", "", "tests/data"],
+        "tests/data/a.json
+        tests/data/b.yaml",
+    );
+
+    te.assert_failure(&["--path-glob", "--full-path", "src/*.rs"]);
+}
+
+/// Unicode normalization (--normalize, --case-fold, --ignore-diacritics)
+#[test]
+fn test_normalize() {
+    let te = TestEnv::new(&[], &["Cafe\u{301}.txt", "Caf\u{e9}.md", "Straße.txt"]);
+
+    te.assert_output(&["Caf\u{e9}"], "Caf\u{e9}.md");
+    te.assert_output(
+        &["--normalize=nfc", "Caf\u{e9}"],
+        "Cafe\u{301}.txt
+        Caf\u{e9}.md",
+    );
+    te.assert_output(
+        &["--normalize=nfd", "Caf\u{e9}"],
+        "Cafe\u{301}.txt
+        Caf\u{e9}.md",
+    );
+
+    te.assert_output(&["strasse"], "");
+    te.assert_output(&["--case-fold", "STRASSE"], "Straße.txt");
+
+    te.assert_output(
+        &["--ignore-diacritics", "^cafe"],
+        "Cafe\u{301}.txt
+        Caf\u{e9}.md",
+    );
+    te.assert_output(
+        &["--ignore-diacritics", "--extension", "md", "café"],
+        "Caf\u{e9}.md",
+    );
+
+    te.assert_output(
+        &["--glob", "--ignore-diacritics", "Café*"],
+        "Cafe\u{301}.txt
+        Caf\u{e9}.md",
+    );
+    te.assert_output(
+        &["--glob", "--normalize=nfc", "Caf\u{e9}.*"],
+        "Cafe\u{301}.txt
+        Caf\u{e9}.md",
+    );
+    te.assert_output(&["--path-glob", "--case-fold", "STRASSE.*"], "Straße.txt");
+
+    te.assert_failure(&["--normalize=nfx", "cafe"]);
+}
+
+/// Filter by content (--mime, --kind)
+#[test]
+fn test_content_filter() {
+    let te = TestEnv::new(&["data"], &["data/notes", "README.md"]);
+    let root = te.test_root();
+    fs::write(
+        root.join("data/picture"),
+        b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR",
+    )
+    .unwrap();
+    fs::write(root.join("data/photo.bin"), b"\xff\xd8\xff\xe0\x00\x10JFIF").unwrap();
+    fs::write(root.join("data/program"), b"\x7fELF\x02\x01\x01\x00\x00").unwrap();
+    fs::write(root.join("data/blob"), b"\x01\x02\x00\xff").unwrap();
+    fs::write(root.join("data/backup"), b"\x1f\x8b\x08\x00").unwrap();
+
+    te.assert_output(&["--mime", "image/png"], "data/picture");
+    te.assert_output(
+        &["--mime", "image/*"],
+        "data/photo.bin
+        data/picture",
+    );
+    te.assert_output(
+        &["--mime", "image/jpeg", "--mime", "application/gzip"],
+        "data/backup
+        data/photo.bin",
+    );
+
+    te.assert_output(&["--kind", "elf"], "data/program");
+    te.assert_output(&["--kind", "archive"], "data/backup");
+    te.assert_output(
+        &["--kind", "text"],
+        "README.md
+        data/notes",
+    );
+    te.assert_output(
+        &["--kind", "binary", "--kind", "text", "^[bn]"],
+        "data/backup
+        data/blob
+        data/notes",
+    );
+    te.assert_output(
+        &["--kind", "image", "--mime", "image/jpeg"],
+        "data/photo.bin",
+    );
+
+    te.assert_failure(&["--kind", "picture"]);
+    te.assert_failure(&["--mime", "image/"]);
+}
+
+/// Named groups of file types (--kind, --kind-add, --kind-list)
+#[test]
+fn test_kinds() {
+    let te = TestEnv::new(
+        &["src", "proto"],
+        &[
+            "src/main.rs",
+            "src/lib.cpp",
+            "src/lib.h",
+            "Makefile",
+            "proto/api.proto",
+            "proto/api.pb",
+            "logo.png",
+        ],
+    );
+    fs::write(te.test_root().join("logo"), b"\x89PNG\r\n\x1a\n\x00\x00").unwrap();
+
+    te.assert_output(&["--kind", "rust"], "src/main.rs");
+    te.assert_output(
+        &["--kind", "cpp", "--kind", "make"],
+        "Makefile
+        src/lib.cpp
+        src/lib.h",
+    );
+
+    // Images are recognized by their name and by their content
+    te.assert_output(
+        &["--kind", "image"],
+        "logo
+        logo.png",
+    );
+
+    te.assert_output(
+        &["--kind-add", "proto:*.proto,*.pb", "--kind", "proto"],
+        "proto/api.pb
+        proto/api.proto",
+    );
+    te.assert_output(
+        &["--kind-add", "rust:*.proto", "--kind", "rust"],
+        "proto/api.proto
+        src/main.rs",
+    );
+    te.assert_failure(&["--kind", "proto"]);
+    te.assert_failure(&["--kind-add", "proto", "--kind", "proto"]);
+
+    let output =
+        te.assert_success_and_get_output(".", &["--kind-add", "proto:*.proto", "--kind-list"]);
+    let list = String::from_utf8_lossy(&output.stdout);
+    assert!(list.lines().any(|l| l == "rust: *.rs"));
+    assert!(list.lines().any(|l| l == "proto: *.proto"));
+}
+
+/// Text and binary files (--text-only, --binary-only)
+#[test]
+fn test_text_and_binary_only() {
+    let te = TestEnv::new(&["src"], &["src/empty.c"]);
+    let root = te.test_root();
+    fs::write(root.join("src/main.c"), "int main() { return 0; }\n").unwrap();
+    fs::write(root.join("src/grüße.txt"), "Grüße\n").unwrap();
+    fs::write(root.join("src/main.o"), b"\x7fELF\x02\x01\x01\x00\x00\x00").unwrap();
+    fs::write(root.join("src/latin1.txt"), b"Gr\xfc\xdfe\n").unwrap();
+
+    te.assert_output(
+        &["--text-only"],
+        "src/empty.c
+        src/grüße.txt
+        src/main.c",
+    );
+    te.assert_output(
+        &["--binary-only"],
+        "src/latin1.txt
+        src/main.o",
+    );
+    te.assert_output(&["--text-only", "--extension", "c", "main"], "src/main.c");
+    te.assert_output(&["--binary-only", "--kind", "elf"], "src/main.o");
+    te.assert_output(&["--text-only", "--mime", "application/x-elf"], "");
+
+    te.assert_failure(&["--text-only", "--binary-only"]);
+}
+
+/// Filters for symbolic links (--broken-symlinks, --symlink-target, --symlink-escapes)
+#[cfg(unix)]
+#[test]
+fn test_symlink_filters() {
+    use std::os::unix::fs::symlink;
+
+    let te = TestEnv::new(
+        &["deploy/bin", "deploy/lib", "one/two"],
+        &["deploy/lib/tool.so"],
+    );
+    let bin = te.test_root().join("deploy/bin");
+    symlink("../lib/tool.so", bin.join("tool")).unwrap();
+    symlink("../lib/missing.so", bin.join("dangling")).unwrap();
+    symlink("/etc", bin.join("etc")).unwrap();
+    symlink("../../../nowhere", bin.join("gone")).unwrap();
+    symlink("../../one/two", bin.join("shared")).unwrap();
+
+    te.assert_output(
+        &["--broken-symlinks"],
+        "deploy/bin/dangling
+        deploy/bin/gone",
+    );
+    te.assert_output(
+        &["--symlink-target", "^\\.\\./lib/"],
+        "deploy/bin/dangling
+        deploy/bin/tool",
+    );
+    te.assert_output(
+        &["--broken-symlinks", "--symlink-target", "lib"],
+        "deploy/bin/dangling",
+    );
+
+    // Links are checked against the search path they were found in
+    te.assert_output(
+        &["--symlink-escapes"],
+        "deploy/bin/etc
+        deploy/bin/gone",
+    );
+    te.assert_output(
+        &["--symlink-escapes", "", "deploy"],
+        "deploy/bin/etc
+        deploy/bin/gone
+        deploy/bin/shared",
+    );
+
+    // Broken links are also checked when symbolic links are followed
+    fs::remove_file(bin.join("etc")).unwrap();
+    te.assert_output(&["--follow", "--symlink-escapes"], "deploy/bin/gone");
+    te.assert_output(
+        &["--follow", "--symlink-escapes", "", "deploy"],
+        "deploy/bin/gone
+        deploy/bin/shared",
+    );
+
+    te.assert_output(
+        &["--broken-symlinks", "--format", "{} -> {target}"],
+        "deploy/bin/dangling -> ../lib/missing.so
+        deploy/bin/gone -> ../../../nowhere",
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_xattr() {
+    let te = TestEnv::new(&["dir"], &["a.txt", "b.txt", "dir/c.txt"]);
+    let root = te.test_root();
+
+    // Not all file systems support extended attributes in the user namespace
+    if xattr::set(root.join("a.txt"), "user.checksum", b"1234").is_err() {
+        return;
+    }
+    xattr::set(root.join("dir/c.txt"), "user.checksum", b"5678").unwrap();
+    xattr::set(root.join("dir/c.txt"), "user.processed", b"").unwrap();
+
+    te.assert_output(
+        &["--xattr", "user.checksum"],
+        "a.txt
+        dir/c.txt",
+    );
+    te.assert_output(&["--xattr", "user.checksum=5678"], "dir/c.txt");
+    te.assert_output(
+        &["--type", "f", "--xattr", "!user.processed"],
+        "a.txt
+        b.txt",
+    );
+    te.assert_output(
+        &["--xattr", "user.checksum", "--xattr", "!user.processed"],
+        "a.txt",
+    );
+    te.assert_output(&["--has-acl"], "");
+
+    te.assert_failure(&["--xattr", "=value"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_disk_usage_and_sparse() {
+    use std::os::unix::fs::MetadataExt;
+
+    let te = TestEnv::new(&[], &[]);
+    let root = te.test_root();
+
+    // A file of 4 MiB without any allocated blocks
+    let image = fs::File::create(root.join("disk.img")).unwrap();
+    image.set_len(4 * 1024 * 1024).unwrap();
+    // Not all file systems support sparse files
+    if image.metadata().unwrap().blocks() > 0 {
+        return;
+    }
+    fs::write(root.join("data.bin"), vec![1u8; 256 * 1024]).unwrap();
+
+    te.assert_output(&["--sparse"], "disk.img");
+    te.assert_output(
+        &["--size", "+100ki"],
+        "data.bin
+        disk.img",
+    );
+    te.assert_output(&["--disk-usage", "--size", "+100ki"], "data.bin");
+    te.assert_output(&["--disk-usage", "--size", "-1k"], "disk.img");
+}
+
+/// Size ranges, fractional sizes and sizes of reference files
+#[test]
+fn test_size_ranges_and_reference_files() {
+    let te = TestEnv::new(&[], &[]);
+
+    create_file_with_size(te.test_root().join("0_bytes.foo"), 0);
+    create_file_with_size(te.test_root().join("1000_bytes.foo"), 1000);
+    create_file_with_size(te.test_root().join("1500_bytes.foo"), 1500);
+    create_file_with_size(te.test_root().join("3000_bytes.foo"), 3000);
+
+    te.assert_output(
+        &["--size", "1k..1.5k"],
+        "1000_bytes.foo
+        1500_bytes.foo",
+    );
+    te.assert_output(&["--size", "+1.2k", "--size", "-2ki"], "1500_bytes.foo");
+    te.assert_output(&["--size", "1.5kb"], "1500_bytes.foo");
+
+    te.assert_output(
+        &["--larger-than", "1000_bytes.foo"],
+        "1500_bytes.foo
+        3000_bytes.foo",
+    );
+    te.assert_output(
+        &["--smaller-than", "1500_bytes.foo"],
+        "0_bytes.foo
+        1000_bytes.foo",
+    );
+    te.assert_output(
+        &[
+            "--larger-than",
+            "0_bytes.foo",
+            "--smaller-than",
+            "3000_bytes.foo",
+        ],
+        "1000_bytes.foo
+        1500_bytes.foo",
+    );
+
+    te.assert_failure_with_error(
+        &["--size", "+18"],
+        "[fd error]: The size '18' has no unit. Use e.g. '18b' for bytes or '18k' for kilobytes.",
+    );
+    te.assert_failure_with_error(
+        &["--size", "2k..1k"],
+        "[fd error]: Invalid range '2k..1k': the lower bound (2 kB) is larger than the upper bound \
+         (1 kB).",
+    );
+    te.assert_failure(&["--larger-than", "missing.foo"]);
+    te.assert_failure(&["--smaller-than", "0_bytes.foo"]);
+}
+
+/// Filtering directories by their contents (--dir-entries, --dir-size)
+#[test]
+fn test_dir_entries_and_dir_size() {
+    let te = TestEnv::new(&["cache/a", "cache/b", "small", "empty"], &[]);
+
+    for name in &["one", "two", "three"] {
+        create_file_with_size(te.test_root().join("cache/a").join(name), 1000);
+    }
+    create_file_with_size(te.test_root().join("cache/b/.hidden"), 2000);
+    create_file_with_size(te.test_root().join("small/file"), 10);
+
+    te.assert_output(
+        &["--dir-entries", "+2"],
+        "cache
+        cache/a",
+    );
+    te.assert_output(&["--dir-entries", "0"], "empty");
+    te.assert_output(
+        &["--dir-entries", "1..2", "--dir-size", "+1b"],
+        "cache
+        cache/b
+        small",
+    );
+
+    te.assert_output(
+        &["--dir-size", "+2k"],
+        "cache
+        cache/a
+        cache/b",
+    );
+    te.assert_output(&["--dir-size", "+4k"], "cache");
+    te.assert_output(&["--dir-size", "3k..4k"], "cache/a");
+    te.assert_output(
+        &["--dir-size", "-10b"],
+        "empty
+        small",
+    );
+
+    te.assert_failure(&["--dir-entries", "+1k"]);
+}
+
+/// Single-threaded traversal in a stable order (--traversal, --dirs-first, --dirs-last)
+#[cfg(unix)]
+#[test]
+fn test_traversal_order() {
+    let te = TestEnv::new(&["b/d", "a/c"], &["z.txt", "a/x", "a/c/y", "b/d/f"]);
+    remove_symlink(te.test_root().join("symlink"));
+
+    te.assert_output_raw(
+        &["--traversal", "dfs"],
+        b"a\na/c\na/c/y\na/x\nb\nb/d\nb/d/f\nz.txt\n",
+    );
+    te.assert_output_raw(
+        &["--traversal", "bfs"],
+        b"a\nb\nz.txt\na/c\na/x\nb/d\na/c/y\nb/d/f\n",
+    );
+    te.assert_output_raw(
+        &["--traversal", "bfs", "--max-depth", "1"],
+        b"a\nb\nz.txt\n",
+    );
+    te.assert_output_raw(
+        &["--traversal", "bfs", "--type", "f"],
+        b"z.txt\na/x\na/c/y\nb/d/f\n",
+    );
+
+    te.assert_output_raw(
+        &["--dirs-first", "--type", "f"],
+        b"a/c/y\na/x\nb/d/f\nz.txt\n",
+    );
+    // Children come before their parents
+    te.assert_output_raw(
+        &["--dirs-last"],
+        b"z.txt\na/x\na/c/y\na/c\na\nb/d/f\nb/d\nb\n",
+    );
+    te.assert_output_raw(
+        &["--traversal", "bfs", "--dirs-last"],
+        b"z.txt\na\nb\na/x\na/c\nb/d\na/c/y\nb/d/f\n",
+    );
+
+    // Pruned and excluded directories are not traversed
+    te.assert_output_raw(&["--traversal", "bfs", "--prune", "^a$"], b"a\n");
+    te.assert_output_raw(
+        &[
+            "--traversal",
+            "dfs",
+            "--exclude-if",
+            "type=d",
+            "--dirs-first",
+        ],
+        b"z.txt\n",
+    );
+    te.assert_output_raw(
+        &["--traversal", "bfs", "--exclude-regex", "^c$"],
+        b"a\nb\nz.txt\na/x\nb/d\nb/d/f\n",
+    );
+
+    te.assert_failure(&["--traversal", "bfs", "--include", "*.d"]);
+    te.assert_failure(&["--dirs-first", "--include", "*.d"]);
+}
+
+/// Time and depth filters for archive members (--search-archives)
+#[test]
+fn test_search_archives_time_and_depth() {
+    let te = TestEnv::new(&[], &[]);
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut builder = tar::Builder::new(fs::File::create(te.test_root().join("rel.tar")).unwrap());
+    for (name, mtime) in &[("d/libfoo.so", 978_307_200), ("d/libbar.so", now)] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_mode(0o644);
+        header.set_mtime(*mtime);
+        header.set_cksum();
+        builder.append_data(&mut header, name, &[][..]).unwrap();
+    }
+    builder.into_inner().unwrap();
+
+    te.assert_output(
+        &["--search-archives", r"\.so$"],
+        "rel.tar!/d/libbar.so
+        rel.tar!/d/libfoo.so",
+    );
+    te.assert_output(
+        &["--search-archives", "--changed-within", "1d", r"\.so$"],
+        "rel.tar!/d/libbar.so",
+    );
+    te.assert_output(
+        &["--search-archives", "--changed-before", "1y", r"\.so$"],
+        "rel.tar!/d/libfoo.so",
+    );
+
+    // The depth of a member is the depth of the archive plus the depth inside of it
+    te.assert_output(&["--search-archives", "--max-depth", "2", r"\.so$"], "");
+    te.assert_output(
+        &["--search-archives", "--exact-depth", "3", r"\.so$"],
+        "rel.tar!/d/libbar.so
+        rel.tar!/d/libfoo.so",
+    );
+    te.assert_output(&["--search-archives", "--min-depth", "4", r"\.so$"], "");
+}
+
+/// Hard links in multiple search paths are all reported
+#[cfg(unix)]
+#[test]
+fn test_multiple_search_paths_hard_links() {
+    let te = TestEnv::new(&["a", "b"], &["a/file"]);
+    fs::hard_link(te.test_root().join("a/file"), te.test_root().join("b/link")).unwrap();
+    fs::hard_link(te.test_root().join("a/file"), te.test_root().join("a/link")).unwrap();
+
+    te.assert_output(
+        &["--type", "f", "", "a", "b"],
+        "a/file
+         a/link
+         b/link",
+    );
+    te.assert_output(
+        &["--follow", "--type", "f", "", "a", "b"],
+        "a/file
+         a/link
+         b/link",
+    );
+}
+
+/// Hard links are counted once in the size of a directory (--dir-size)
+#[cfg(unix)]
+#[test]
+fn test_dir_size_hard_links() {
+    let te = TestEnv::new(&["data/x", "data/y"], &[]);
+    let root = te.test_root();
+    create_file_with_size(root.join("data/x/f"), 1000);
+    fs::hard_link(root.join("data/x/f"), root.join("data/x/g")).unwrap();
+    fs::hard_link(root.join("data/x/f"), root.join("data/y/f")).unwrap();
+
+    te.assert_output(
+        &["--dir-size", "1000b"],
+        "data
+        data/x
+        data/y",
+    );
+    te.assert_output(&["--dir-size", "+1001b"], "");
+}
+
+/// Copying and archiving a given list of paths (--paths-from with --copy-to and --archive)
+#[test]
+fn test_paths_from_actions() {
+    let te = TestEnv::new(&["src/nested", "other"], &["src/a.txt", "src/nested/b.txt"]);
+    let root = te.test_root();
+    create_file_with_size(root.join("other/c.txt"), 10);
+    let absolute = root.join("other/c.txt");
+    fs::write(
+        root.join("list.txt"),
+        format!(
+            "src/a.txt\nsrc/nested/b.txt\n{}\n",
+            absolute.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+
+    // Relative paths keep their path below the current directory, absolute paths only keep
+    // their name.
+    te.assert_output(&["--paths-from", "list.txt", "--copy-to", "backup"], "");
+    assert!(root.join("backup/src/a.txt").is_file());
+    assert!(root.join("backup/src/nested/b.txt").is_file());
+    assert!(root.join("backup/c.txt").is_file());
+
+    te.assert_output(&["--paths-from", "list.txt", "--archive", "out.tar"], "");
+    let mut archive = tar::Archive::new(fs::File::open(root.join("out.tar")).unwrap());
+    let mut members: Vec<String> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            entry
+                .unwrap()
+                .path()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    members.sort();
+    assert_eq!(members, vec!["c.txt", "src/a.txt", "src/nested/b.txt"]);
+
+    te.assert_output(
+        &[
+            "--paths-from",
+            "list.txt",
+            "--format",
+            "{root}: {/}",
+            "txt$",
+        ],
+        &format!(
+            ".: a.txt
+            .: b.txt
+            {}: c.txt",
+            root.join("other").to_str().unwrap()
+        ),
+    );
+}
+
+/// Directories that are reached through symbolic links are traversed once (--follow)
+#[cfg(unix)]
+#[test]
+fn test_follow_deduplication() {
+    use std::os::unix::fs::symlink;
+
+    let te = TestEnv::new(&["a", "b/outside", "c"], &["a/y.rs", "b/outside/z.rs"]);
+    remove_symlink(te.test_root().join("symlink"));
+    symlink("../a", te.test_root().join("c/link")).unwrap();
+    symlink("../b/outside", te.test_root().join("c/first")).unwrap();
+    symlink("../b/outside", te.test_root().join("a/second")).unwrap();
+
+    te.assert_output(&["--follow", "-e", "rs"], "a/y.rs\nb/outside/z.rs");
+    te.assert_output(&["--follow", "--type", "d", "link"], "c/link");
+
+    // Links to a directory outside of the search path are traversed once
+    let output = te.assert_success_and_get_output(".", &["--follow", "-e", "rs", "", "a", "c"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.contains(&"a/y.rs"));
+    assert!(lines.contains(&"a/second/z.rs") || lines.contains(&"c/first/z.rs"));
+}
+
+/// Hidden and excluded archive members (--search-archives, --hidden, --exclude)
+#[test]
+fn test_search_archives_hidden_and_excluded() {
+    let te = TestEnv::new(&[], &[]);
+
+    let mut builder = tar::Builder::new(fs::File::create(te.test_root().join("r.tar")).unwrap());
+    for name in &[
+        "src/main.rs",
+        "src/.secret/key.rs",
+        "src/node_modules/dep.rs",
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, &[][..]).unwrap();
+    }
+    builder.into_inner().unwrap();
+
+    te.assert_output(
+        &["--search-archives", r"\.rs$"],
+        "r.tar!/src/main.rs
+        r.tar!/src/node_modules/dep.rs",
+    );
+    te.assert_output(
+        &["--search-archives", "--hidden", r"\.rs$"],
+        "r.tar!/src/.secret/key.rs
+        r.tar!/src/main.rs
+        r.tar!/src/node_modules/dep.rs",
+    );
+    te.assert_output(
+        &["--search-archives", "-E", "node_modules", r"\.rs$"],
+        "r.tar!/src/main.rs",
+    );
+    te.assert_output(
+        &["--search-archives", "-E", "*.rs", "--hidden", "^r"],
+        "r.tar",
+    );
+}
+
+/// Archive members excluded by a regex or a condition (--search-archives, --exclude-regex,
+/// --exclude-if)
+#[test]
+fn test_search_archives_exclusions() {
+    let te = TestEnv::new(&[], &[]);
+
+    let mut builder = tar::Builder::new(fs::File::create(te.test_root().join("r.tar")).unwrap());
+    for (name, size) in &[
+        ("src/main.rs", 10),
+        ("src/big.rs", 4096),
+        ("target/debug/out.rs", 10),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(*size);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, &vec![0; *size as usize][..])
+            .unwrap();
+    }
+    builder.into_inner().unwrap();
+
+    te.assert_output(
+        &["--search-archives", "--exclude-regex", "^target$", r"\.rs$"],
+        "r.tar!/src/big.rs
+        r.tar!/src/main.rs",
+    );
+    te.assert_output(
+        &[
+            "--search-archives",
+            "--exclude-regex",
+            r"src/.*\.rs$",
+            r"\.rs$",
+        ],
+        "r.tar!/target/debug/out.rs",
+    );
+    te.assert_output(
+        &["--search-archives", "--exclude-if", "size=-1k", r"\.rs$"],
+        "r.tar!/src/big.rs",
+    );
+}
+
+/// File sizes in the output (--format '{size}')
+#[test]
+fn test_format_size() {
+    let te = TestEnv::new(&["dir/sub"], &[]);
+    create_file_with_size(te.test_root().join("dir/small.bin"), 999);
+    create_file_with_size(te.test_root().join("dir/large.bin"), 1500);
+
+    // Directories have no size
+    te.assert_output(
+        &["--format", "{/} [{size}]", "", "dir"],
+        "large.bin [1.5 kB]
+        small.bin [999 B]
+        sub []",
+    );
+}